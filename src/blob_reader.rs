@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Read, Seek};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{local_payload_size, FilePageCell, FilePageType, Pager};
+use crate::record::serial_type_length;
+use crate::varint::ReadVarExt;
+
+/// Reads the payload of a cell a page at a time, following its overflow chain as it goes.
+struct PayloadReader<'a, R> {
+    pager: &'a mut Pager<R>,
+    /// Raw bytes of the page holding the part of the payload being read.
+    page: Vec<u8>,
+    position: usize,
+    end: usize,
+    next_page_number: u32,
+    /// Bytes of the payload in the overflow pages not read yet.
+    remaining: u64,
+    /// Bytes of the payload read so far.
+    consumed: u64,
+    visited: HashSet<u32>,
+}
+
+impl<R> PayloadReader<'_, R>
+    where R: Read + Seek
+{
+    fn next_page(&mut self) -> std::io::Result<()> {
+        let header = self.pager.header();
+        let page_number = self.next_page_number;
+
+        if page_number == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Overflow chain ended {} bytes short of payload", self.remaining)));
+        }
+        if page_number > header.database_size() {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Overflow page {} is outside of 1..={}", page_number, header.database_size())));
+        }
+        if !self.visited.insert(page_number) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Overflow chain loops back to page {}", page_number)));
+        }
+
+        let chunk_length = self.remaining.min(header.usable_size() as u64 - 4);
+        self.page = self.pager.read_raw(page_number)?;
+        self.next_page_number = BigEndian::read_u32(&self.page[0..4]);
+        self.position = 4;
+        self.end = 4 + chunk_length as usize;
+        self.remaining -= chunk_length;
+
+        Ok(())
+    }
+}
+
+impl<R> Read for PayloadReader<'_, R>
+    where R: Read + Seek
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position == self.end {
+            if self.remaining == 0 {
+                return Ok(0);
+            }
+            self.next_page()?;
+        }
+
+        let length = buf.len().min(self.end - self.position);
+        buf[..length].copy_from_slice(&self.page[self.position..self.position + length]);
+        self.position += length;
+        self.consumed += length as u64;
+
+        Ok(length)
+    }
+}
+
+/// Streams a blob stored in a table row, reading its overflow pages only as they are reached,
+/// so that at most one page of it is held in memory. See [`Database::blob_reader`](crate::Database::blob_reader).
+pub struct BlobReader<'a, R> {
+    payload: PayloadReader<'a, R>,
+    length: u64,
+    remaining: u64,
+}
+
+impl<'a, R> BlobReader<'a, R>
+    where R: Read + Seek
+{
+    /// Reads the record header of a table leaf cell read without its payload, and skips the
+    /// values before the blob in column `column`.
+    pub(crate) fn new(pager: &'a mut Pager<R>, cell: &FilePageCell, column: usize) -> std::io::Result<Self> {
+        let header = pager.header().clone();
+        let payload_length = cell.payload_length
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Cell at offset {} has no payload", cell.file_offset)))?;
+
+        // The local part of the payload ends the cell, followed by the first overflow page number, if any
+        let local_length = local_payload_size(payload_length, header.usable_size(), FilePageType::TableLeaf);
+        let cell_end = cell.file_offset + cell.size as u64;
+        let local_start = cell_end - local_length - if cell.first_overflow_page_number.is_some() { 4 } else { 0 };
+
+        let page_number = (cell.file_offset / header.page_size() as u64 + 1) as u32;
+        let start = (local_start - header.page_offset(page_number)) as usize;
+
+        let mut payload = PayloadReader {
+            page: pager.read_raw(page_number)?,
+            pager,
+            position: start,
+            end: start + local_length as usize,
+            next_page_number: cell.first_overflow_page_number.unwrap_or(0),
+            remaining: payload_length - local_length,
+            consumed: 0,
+            visited: HashSet::new(),
+        };
+
+        let header_length = payload.read_var64()? as u64;
+        let mut serial_types = Vec::new();
+
+        while payload.consumed < header_length {
+            serial_types.push(payload.read_var64()? as u64);
+        }
+
+        let skipped = serial_types.iter().take(column)
+            .map(|&serial_type| serial_type_length(serial_type).ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Unknown record type: {}", serial_type))))
+            .sum::<std::io::Result<u64>>()?;
+
+        let length = match serial_types.get(column) {
+            Some(&serial_type) if serial_type >= 12 && serial_type % 2 == 0 => (serial_type - 12) / 2,
+            Some(&serial_type) => return Err(Error::new(ErrorKind::InvalidInput, format!("Column {} holds a value of serial type {}, not a blob", column, serial_type))),
+            None => return Err(Error::new(ErrorKind::InvalidInput, format!("Column {} is not stored in a record of {} columns", column, serial_types.len()))),
+        };
+
+        std::io::copy(&mut (&mut payload).take(skipped), &mut std::io::sink())?;
+
+        Ok(BlobReader {
+            payload,
+            length,
+            remaining: length,
+        })
+    }
+
+    /// Length of the whole blob in bytes.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
+
+impl<R> Read for BlobReader<'_, R>
+    where R: Read + Seek
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = buf.len().min(self.remaining.try_into().unwrap_or(usize::MAX));
+        if length == 0 {
+            return Ok(0);
+        }
+
+        let length = self.payload.read(&mut buf[..length])?;
+        if length == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, format!("Blob ended {} bytes short of its length of {}", self.remaining, self.length)));
+        }
+
+        self.remaining -= length as u64;
+        Ok(length)
+    }
+}
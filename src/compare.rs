@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+
+use crate::{Record, RecordEntry};
+
+/// Orders the storage classes of values the way SQLite does: NULL, then numbers, then text, then blobs.
+fn type_class(entry: &RecordEntry) -> u8 {
+    match entry {
+        RecordEntry::Null => 0,
+        RecordEntry::Integer(_) | RecordEntry::Float(_) => 1,
+        RecordEntry::Text(_) => 2,
+        RecordEntry::Blob(_) => 3,
+    }
+}
+
+/// A built-in collating sequence of SQLite, which decides how text values compare.
+/// See [Collating Sequences](https://www.sqlite.org/datatype3.html#collating_sequences).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Collation {
+    /// Compares the bytes of the text.
+    #[default]
+    Binary,
+    /// Like `BINARY`, but folds the 26 ASCII upper case letters to lower case first.
+    NoCase,
+    /// Like `BINARY`, but ignores trailing spaces.
+    RTrim,
+}
+
+impl Collation {
+    /// Returns the built-in collation with the given case-insensitive name, or `None` for
+    /// a collation defined by an application.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "BINARY" => Some(Collation::Binary),
+            "NOCASE" => Some(Collation::NoCase),
+            "RTRIM" => Some(Collation::RTrim),
+            _ => None,
+        }
+    }
+}
+
+/// Compares two text values using the given collation.
+pub fn compare_text(a: &str, b: &str, collation: Collation) -> Ordering {
+    match collation {
+        Collation::Binary => a.as_bytes().cmp(b.as_bytes()),
+        Collation::NoCase => a.bytes().map(|byte| byte.to_ascii_lowercase()).cmp(b.bytes().map(|byte| byte.to_ascii_lowercase())),
+        Collation::RTrim => a.trim_end_matches(' ').as_bytes().cmp(b.trim_end_matches(' ').as_bytes()),
+    }
+}
+
+/// Compares an integer with a float exactly, like SQLite does. Converting the integer to a
+/// float would round integers beyond 2^53, so that 9007199254740993 would equal 9007199254740992.0,
+/// so the integral part of the float is compared as an integer first. NaN is treated as NULL,
+/// which is less than any integer.
+fn compare_integer_float(integer: i64, float: f64) -> Ordering {
+    if float.is_nan() {
+        return Ordering::Greater;
+    }
+    if float < -9223372036854775808.0 {
+        return Ordering::Greater;
+    }
+    if float >= 9223372036854775808.0 {
+        return Ordering::Less;
+    }
+
+    // Floats with a fractional part are below 2^52, where every integer converts exactly
+    match integer.cmp(&(float as i64)) {
+        Ordering::Equal => (integer as f64).partial_cmp(&float).unwrap_or(Ordering::Equal),
+        ordering => ordering,
+    }
+}
+
+/// Compares two values using SQLite's sort order. Values of different storage classes are
+/// ordered by class, integers and floats compare numerically and exactly, text compares byte-wise
+/// and blobs are compared with `memcmp`.
+pub fn compare_entries(a: &RecordEntry, b: &RecordEntry) -> Ordering {
+    compare_entries_collated(a, b, Collation::Binary)
+}
+
+/// Compares two values like [`compare_entries`], but compares text using the given collation.
+pub fn compare_entries_collated(a: &RecordEntry, b: &RecordEntry, collation: Collation) -> Ordering {
+    match (a, b) {
+        (RecordEntry::Integer(a), RecordEntry::Integer(b)) => a.cmp(b),
+        (RecordEntry::Integer(a), RecordEntry::Float(b)) => compare_integer_float(*a, *b),
+        (RecordEntry::Float(a), RecordEntry::Integer(b)) => compare_integer_float(*b, *a).reverse(),
+        (RecordEntry::Float(a), RecordEntry::Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (RecordEntry::Text(a), RecordEntry::Text(b)) => compare_text(a, b, collation),
+        (RecordEntry::Blob(a), RecordEntry::Blob(b)) => a.cmp(b),
+        (a, b) => type_class(a).cmp(&type_class(b)),
+    }
+}
+
+/// Compares two records column by column using [`compare_entries`], the way SQLite orders the
+/// keys of an index with ascending columns and the `BINARY` collation. If one record is a
+/// prefix of the other, the shorter one comes first.
+pub fn compare_records(a: &Record, b: &Record) -> Ordering {
+    compare_prefix(&a.entries, &b.entries).then(a.entries.len().cmp(&b.entries.len()))
+}
+
+/// Compares the columns two lists of values have in common, ignoring the rest of the longer one.
+pub(crate) fn compare_prefix(a: &[RecordEntry], b: &[RecordEntry]) -> Ordering {
+    compare_prefix_collated(a, b, &[])
+}
+
+/// Compares the columns two lists of values have in common like [`compare_prefix`], using the
+/// collation of each column in `collations`, or `BINARY` for the columns past its end.
+pub(crate) fn compare_prefix_collated(a: &[RecordEntry], b: &[RecordEntry], collations: &[Collation]) -> Ordering {
+    compare_prefix_ordered(a, b, collations, &[])
+}
+
+/// Compares the columns two lists of values have in common like [`compare_prefix_collated`],
+/// in reverse for the columns marked in `descending`, as for the `DESC` columns of an index.
+pub(crate) fn compare_prefix_ordered(a: &[RecordEntry], b: &[RecordEntry], collations: &[Collation], descending: &[bool]) -> Ordering {
+    a.iter()
+        .zip(b.iter())
+        .enumerate()
+        .map(|(index, (a, b))| {
+            let ordering = compare_entries_collated(a, b, collations.get(index).copied().unwrap_or_default());
+            if descending.get(index).copied().unwrap_or(false) {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(entries: Vec<RecordEntry>) -> Record {
+        Record {
+            entries,
+            serial_types: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn storage_classes_are_ordered() {
+        let entries = [
+            RecordEntry::Null,
+            RecordEntry::Integer(-5),
+            RecordEntry::Float(1.5),
+            RecordEntry::Integer(2),
+            RecordEntry::Text(String::new()),
+            RecordEntry::Text("a".to_string()),
+            RecordEntry::Blob(Vec::new()),
+            RecordEntry::Blob(vec![0]),
+        ];
+
+        for (i, a) in entries.iter().enumerate() {
+            for (j, b) in entries.iter().enumerate() {
+                assert_eq!(compare_entries(a, b), i.cmp(&j), "{:?} and {:?}", a, b);
+            }
+        }
+
+        // Numbers come before text however large they are, and text before blobs
+        assert_eq!(compare_entries(&RecordEntry::Integer(i64::MAX), &RecordEntry::Text("0".to_string())), Ordering::Less);
+        assert_eq!(compare_entries(&RecordEntry::Float(f64::INFINITY), &RecordEntry::Text("0".to_string())), Ordering::Less);
+        assert_eq!(compare_entries(&RecordEntry::Text("\u{10ffff}".to_string()), &RecordEntry::Blob(Vec::new())), Ordering::Less);
+        assert_eq!(compare_entries(&RecordEntry::Integer(2), &RecordEntry::Float(2.0)), Ordering::Equal);
+    }
+
+    #[test]
+    fn later_columns_break_ties() {
+        let a = record(vec![RecordEntry::Text("x".to_string()), RecordEntry::Integer(1), RecordEntry::Integer(9)]);
+        let b = record(vec![RecordEntry::Text("x".to_string()), RecordEntry::Integer(2), RecordEntry::Integer(0)]);
+        let c = record(vec![RecordEntry::Text("y".to_string()), RecordEntry::Null, RecordEntry::Null]);
+        assert_eq!(compare_records(&a, &b), Ordering::Less);
+        assert_eq!(compare_records(&b, &a), Ordering::Greater);
+        assert_eq!(compare_records(&b, &c), Ordering::Less);
+        assert_eq!(compare_records(&a, &a), Ordering::Equal);
+
+        // A NULL in an earlier column decides the order whatever follows
+        let d = record(vec![RecordEntry::Null, RecordEntry::Blob(vec![0xff])]);
+        assert_eq!(compare_records(&d, &a), Ordering::Less);
+    }
+
+    #[test]
+    fn prefixes_come_first() {
+        let short = record(vec![RecordEntry::Text("x".to_string())]);
+        let long = record(vec![RecordEntry::Text("x".to_string()), RecordEntry::Null]);
+        assert_eq!(compare_records(&short, &long), Ordering::Less);
+        assert_eq!(compare_records(&long, &short), Ordering::Greater);
+        assert_eq!(compare_prefix(&short.entries, &long.entries), Ordering::Equal);
+    }
+
+    #[test]
+    fn descending_columns_compare_in_reverse() {
+        let a = [RecordEntry::Text("a".to_string()), RecordEntry::Integer(1)];
+        let b = [RecordEntry::Text("b".to_string()), RecordEntry::Integer(0)];
+        assert_eq!(compare_prefix_ordered(&a, &b, &[], &[]), Ordering::Less);
+        assert_eq!(compare_prefix_ordered(&a, &b, &[], &[true]), Ordering::Greater);
+        assert_eq!(compare_prefix_ordered(&a, &b, &[], &[false, true]), Ordering::Less);
+
+        // Only the columns marked descending are reversed
+        let c = [RecordEntry::Text("a".to_string()), RecordEntry::Integer(0)];
+        assert_eq!(compare_prefix_ordered(&a, &c, &[], &[true]), Ordering::Greater);
+        assert_eq!(compare_prefix_ordered(&a, &c, &[], &[true, true]), Ordering::Less);
+        assert_eq!(compare_prefix_ordered(&[RecordEntry::Null], &[RecordEntry::Integer(0)], &[], &[true]), Ordering::Greater);
+    }
+
+    #[test]
+    fn collations_compare_text() {
+        assert_eq!(compare_text("Alice", "alice", Collation::Binary), Ordering::Less);
+        assert_eq!(compare_text("Alice", "alice", Collation::NoCase), Ordering::Equal);
+        assert_eq!(compare_text("Alice", "alice", Collation::RTrim), Ordering::Less);
+
+        // NOCASE only folds ASCII letters, and RTRIM only ignores trailing spaces
+        assert_eq!(compare_text("\u{c9}t\u{e9}", "\u{e9}t\u{e9}", Collation::NoCase), Ordering::Less);
+        assert_eq!(compare_text("[", "a", Collation::NoCase), Ordering::Less);
+        assert_eq!(compare_text("[", "A", Collation::Binary), Ordering::Greater);
+        assert_eq!(compare_text("alice  ", "alice", Collation::RTrim), Ordering::Equal);
+        assert_eq!(compare_text("alice\t", "alice", Collation::RTrim), Ordering::Greater);
+        assert_eq!(compare_text(" alice", "alice", Collation::RTrim), Ordering::Less);
+    }
+
+    #[test]
+    fn collations_by_name() {
+        assert_eq!(Collation::from_name("nocase"), Some(Collation::NoCase));
+        assert_eq!(Collation::from_name("RTrim"), Some(Collation::RTrim));
+        assert_eq!(Collation::from_name("BINARY"), Some(Collation::Binary));
+        assert_eq!(Collation::from_name("unicode"), None);
+    }
+
+    #[test]
+    fn integers_and_floats_compare_exactly() {
+        let compare = |a: i64, b: f64| compare_entries(&RecordEntry::Integer(a), &RecordEntry::Float(b));
+
+        // 2^53 + 1 is the first integer that a double cannot hold, and would round to 2^53
+        assert_eq!(compare(9007199254740993, 9007199254740992.0), Ordering::Greater);
+        assert_eq!(compare(9007199254740992, 9007199254740992.0), Ordering::Equal);
+        assert_eq!(compare(9007199254740991, 9007199254740992.0), Ordering::Less);
+        assert_eq!(compare_entries(&RecordEntry::Float(9007199254740992.0), &RecordEntry::Integer(9007199254740993)), Ordering::Less);
+
+        assert_eq!(compare(i64::MAX, 9223372036854775808.0), Ordering::Less);
+        assert_eq!(compare(i64::MIN, -9223372036854775808.0), Ordering::Equal);
+        assert_eq!(compare(i64::MIN, -1e19), Ordering::Greater);
+        assert_eq!(compare(2, 2.5), Ordering::Less);
+        assert_eq!(compare(3, 2.5), Ordering::Greater);
+        assert_eq!(compare(-3, -2.5), Ordering::Less);
+        assert_eq!(compare(-2, -2.5), Ordering::Greater);
+        assert_eq!(compare(0, -0.0), Ordering::Equal);
+        assert_eq!(compare(i64::MAX, f64::INFINITY), Ordering::Less);
+        assert_eq!(compare(i64::MIN, f64::NEG_INFINITY), Ordering::Greater);
+        assert_eq!(compare(i64::MIN, f64::NAN), Ordering::Greater);
+    }
+}
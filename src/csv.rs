@@ -0,0 +1,70 @@
+use std::fmt::Write as _;
+use std::io::{Read, Seek, Write};
+
+use crate::{Record, RecordEntry, Table, TableDef};
+use crate::record::float_text;
+
+/// Writes the column names of a table followed by each of its rows as RFC 4180 CSV. The rows
+/// are those of [`Table::rows`], with their columns in declared order and the `INTEGER PRIMARY KEY`
+/// column, if any, holding the rowid.
+pub fn write_csv<W, R>(writer: &mut W, table: &mut Table<'_, R>) -> std::io::Result<()>
+    where W: Write + ?Sized, R: Read + Seek
+{
+    write_csv_header(writer, &table.def)?;
+
+    for row in table.rows() {
+        write_csv_row(writer, &row?.record)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a line with the column names of a table.
+pub fn write_csv_header<W>(writer: &mut W, table: &TableDef) -> std::io::Result<()>
+    where W: Write + ?Sized
+{
+    let fields = table.columns.iter().map(|column| csv_quote(&column.name));
+    write_csv_line(writer, fields)
+}
+
+/// Writes a line with the entries of a record. NULL is written as an empty field and blobs
+/// are hex-encoded.
+pub fn write_csv_row<W>(writer: &mut W, record: &Record) -> std::io::Result<()>
+    where W: Write + ?Sized
+{
+    let fields = record.entries.iter().map(csv_field);
+    write_csv_line(writer, fields)
+}
+
+fn write_csv_line<W, I>(writer: &mut W, fields: I) -> std::io::Result<()>
+    where W: Write + ?Sized, I: Iterator<Item = String>
+{
+    let line = fields.collect::<Vec<_>>().join(",");
+    write!(writer, "{}\r\n", line)
+}
+
+pub(crate) fn csv_field(entry: &RecordEntry) -> String {
+    match entry {
+        RecordEntry::Null => String::new(),
+        RecordEntry::Integer(x) => x.to_string(),
+        RecordEntry::Float(x) => float_text(*x),
+        RecordEntry::Text(x) => csv_quote(x),
+        RecordEntry::Blob(x) => hex(x),
+    }
+}
+
+/// Quotes a field if it contains a separator, a quote or a line break.
+fn csv_quote(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub(crate) fn hex(data: &[u8]) -> String {
+    data.iter().fold(String::with_capacity(data.len() * 2), |mut result, byte| {
+        write!(result, "{:02x}", byte).unwrap();
+        result
+    })
+}
@@ -0,0 +1,574 @@
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{Cursor, Error, ErrorKind, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::{check_integrity, BlobReader, Collation, DatabaseBuilder, FileHeader, FilePage, Freelist, IndexInfo, IndexIterator, IntegrityProblem, Journal, PageInfo, PageIterator, Pager, PtrmapEntry, ReadOptions, Record, RecordEntry, Schema, SchemaEntryType, Table, TableInfo, TableIterator, TraceDecision, TraceStep, Wal, SCHEMA_ROOT_PAGE};
+use crate::compare::compare_prefix_ordered;
+use crate::sql::{index_column_list, tokenize};
+use crate::FilePageType::{IndexInterior, IndexLeaf, TableInterior, TableLeaf};
+
+/// Maximum depth of a b-tree, beyond which SQLite reports the database as corrupt.
+pub(crate) const MAX_BTREE_DEPTH: usize = 20;
+
+/// A database file whose pages are read lazily through a [`Pager`].
+#[derive(Debug)]
+pub struct Database<R = File> {
+    pager: Pager<R>,
+    /// Hot rollback journal found next to the database file, see [`Database::hot_journal`].
+    journal: Option<Journal>,
+}
+
+impl Database {
+    /// Starts collecting options to open a database with, as in
+    /// `Database::builder().lossy_text(true).cache_capacity(64).open(path)`.
+    pub fn builder() -> DatabaseBuilder {
+        DatabaseBuilder::default()
+    }
+
+    /// Opens a database file, along with its write-ahead log if a `-wal` file exists next to it.
+    /// A hot rollback journal in a `-journal` file is detected as well, but not rolled back.
+    pub fn open<P>(path: P) -> std::io::Result<Self>
+        where P: AsRef<Path>
+    {
+        let file = File::open(&path)?;
+
+        let mut wal_path = PathBuf::from(path.as_ref()).into_os_string();
+        wal_path.push("-wal");
+
+        let mut database = match Wal::open(&wal_path) {
+            Ok(wal) => Database::open_with_wal(file, wal),
+            Err(err) if err.kind() == ErrorKind::NotFound => Database::open_reader(file),
+            Err(err) => Err(err),
+        }?;
+
+        let mut journal_path = PathBuf::from(path.as_ref()).into_os_string();
+        journal_path.push("-journal");
+
+        database.journal = match Journal::open(&journal_path) {
+            Ok(journal) => Some(journal).filter(|journal| journal.is_hot()),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+
+        Ok(database)
+    }
+}
+
+impl<'a> Database<Cursor<&'a [u8]>> {
+    /// Opens a database whose bytes are already in memory, without any filesystem access.
+    pub fn from_bytes(data: &'a [u8]) -> std::io::Result<Self> {
+        Database::open_reader(Cursor::new(data))
+    }
+}
+
+impl<R> Database<R>
+    where R: Read + Seek
+{
+    /// Opens a database from any seekable source, such as a [`std::io::Cursor`] over bytes
+    /// already in memory.
+    pub fn open_reader(reader: R) -> std::io::Result<Self> {
+        Ok(Database {
+            pager: Pager::new(reader)?,
+            journal: None,
+        })
+    }
+
+    /// Opens a database whose committed pages in the given write-ahead log take
+    /// precedence over the ones in the main file.
+    pub fn open_with_wal(reader: R, wal: Wal) -> std::io::Result<Self> {
+        Ok(Database {
+            pager: Pager::with_wal(reader, wal)?,
+            journal: None,
+        })
+    }
+
+    /// Opens a database along with a rollback journal. If the journal is hot, its presence is
+    /// reported by [`Database::hot_journal`], but the pages it holds are not rolled back.
+    pub fn open_with_journal(reader: R, journal: Journal) -> std::io::Result<Self> {
+        Ok(Database {
+            pager: Pager::new(reader)?,
+            journal: Some(journal).filter(|journal| journal.is_hot()),
+        })
+    }
+
+    /// The hot rollback journal of an unfinished transaction, if there is one. The database
+    /// may then contain pages of that transaction and be inconsistent.
+    pub fn hot_journal(&mut self) -> Option<&mut Journal> {
+        self.journal.as_mut()
+    }
+
+    pub fn header(&self) -> &FileHeader {
+        self.pager.header()
+    }
+
+    pub fn pager(&mut self) -> &mut Pager<R> {
+        &mut self.pager
+    }
+
+    /// Changes how records are decoded, such as whether invalid text is an error.
+    pub fn set_read_options(&mut self, options: ReadOptions) {
+        self.pager.set_read_options(options);
+    }
+
+    pub fn page(&mut self, page_number: u32) -> std::io::Result<Rc<FilePage>> {
+        self.pager.get(page_number)
+    }
+
+    /// Classifies a page as a b-tree, freelist, pointer-map or overflow page without parsing its cells.
+    pub fn page_info(&mut self, page_number: u32) -> std::io::Result<PageInfo> {
+        PageInfo::read(&mut self.pager, page_number)
+    }
+
+    /// Reads the list of unused pages.
+    pub fn freelist(&mut self) -> std::io::Result<Freelist> {
+        let header = self.pager.header().clone();
+        Freelist::read(&mut self.pager, &header)
+    }
+
+    /// Compares the raw bytes of every page with those of the same page of `other`, returning
+    /// the numbers of the pages that differ, such as the pages a transaction changed between
+    /// two copies of a database. Pages past the end of only one of the databases differ too.
+    ///
+    /// Page 1 holds the file header, whose change counter makes it differ after every commit.
+    pub fn page_diff<S>(&mut self, other: &mut Database<S>) -> std::io::Result<Vec<u32>>
+        where S: Read + Seek
+    {
+        if self.header().page_size() != other.header().page_size() {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Page sizes differ: {} and {}", self.header().page_size(), other.header().page_size())));
+        }
+
+        let common_size = self.header().database_size().min(other.header().database_size());
+        let total_size = self.header().database_size().max(other.header().database_size());
+        let mut pages = Vec::new();
+
+        for page_number in 1..=common_size {
+            if self.pager.read_raw(page_number)? != other.pager.read_raw(page_number)? {
+                pages.push(page_number);
+            }
+        }
+
+        pages.extend(common_size + 1..=total_size);
+        Ok(pages)
+    }
+
+    /// Reads the pointer-map entry of a page in an auto-vacuum database.
+    pub fn ptrmap_entry(&mut self, page_number: u32) -> std::io::Result<Option<PtrmapEntry>> {
+        PtrmapEntry::read(&mut self.pager, page_number)
+    }
+
+    /// Checks the structure of the schema b-tree, of every b-tree listed in the schema and of
+    /// the freelist, and reports every problem found.
+    ///
+    /// The order of index entries is only checked for indexes and `WITHOUT ROWID` tables of
+    /// UTF-8 databases declared without `DESC` and `COLLATE`, whose entries are ordered as
+    /// [`compare_records`](crate::compare_records) orders them.
+    pub fn check_integrity(&mut self) -> Vec<IntegrityProblem> {
+        let mut root_pages = vec![SCHEMA_ROOT_PAGE];
+        let mut ordered_indexes = Vec::new();
+        let mut problems = Vec::new();
+
+        match Schema::read(self) {
+            Ok(schema) => {
+                root_pages.extend(schema.entries.iter().filter_map(|entry| entry.root_page));
+
+                if self.header().text_encoding() == 1 {
+                    for entry in schema.entries.iter() {
+                        // Automatic indexes are declared by the constraints of their table
+                        let sql = match (entry.typ, &entry.sql) {
+                            (SchemaEntryType::Index, None) => schema.entry(&entry.table_name).and_then(|table| table.sql.as_deref()),
+                            (SchemaEntryType::Index, Some(sql)) => Some(sql.as_str()),
+                            (SchemaEntryType::Table, Some(sql)) if entry.table_def().is_ok_and(|table| table.without_rowid) => Some(sql.as_str()),
+                            _ => None,
+                        };
+
+                        if let (Some(root_page), Some(sql)) = (entry.root_page, sql) {
+                            if has_default_order(sql) {
+                                ordered_indexes.push(root_page);
+                            }
+                        }
+                    }
+                }
+            }
+            Err(err) => problems.push(IntegrityProblem {
+                page_number: SCHEMA_ROOT_PAGE,
+                message: format!("Schema could not be read: {}", err),
+            }),
+        }
+
+        problems.extend(check_integrity(&mut self.pager, &root_pages, &ordered_indexes));
+        problems
+    }
+
+    /// Checks the integrity of the database like [`Database::check_integrity`], returning the
+    /// descriptions of the problems found as `PRAGMA integrity_check` does, or nothing for a
+    /// database without problems.
+    pub fn integrity_check(&mut self) -> Vec<String> {
+        self.check_integrity().into_iter().map(|problem| problem.message).collect()
+    }
+
+    /// Finds a table by name, ignoring ASCII case. Returns `None` if there is no such table,
+    /// and an error if the name belongs to an index, a view or a trigger.
+    pub fn table(&mut self, name: &str) -> std::io::Result<Option<Table<'_, R>>> {
+        let schema = Schema::read(self)?;
+        let Some(entry) = schema.entry(name) else {
+            return Ok(None);
+        };
+
+        let what = match entry.typ {
+            SchemaEntryType::Table => None,
+            SchemaEntryType::Index => Some("an index"),
+            SchemaEntryType::View => Some("a view"),
+            SchemaEntryType::Trigger => Some("a trigger"),
+        };
+
+        if let Some(what) = what {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("{} is {}, not a table", name, what)));
+        }
+
+        let root_page = entry.root_page
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Table {} has no root page", name)))?;
+        let def = entry.table_def()?;
+
+        Ok(Some(Table::new(self, root_page, def)))
+    }
+
+    /// Lists the tables of the schema in schema order, internal and virtual tables included.
+    pub fn tables(&mut self) -> std::io::Result<Vec<TableInfo>> {
+        let schema = Schema::read(self)?;
+
+        Ok(schema.entries.into_iter()
+            .filter(|entry| entry.typ == SchemaEntryType::Table)
+            .map(|entry| TableInfo {
+                name: entry.name,
+                root_page: entry.root_page,
+            })
+            .collect())
+    }
+
+    /// Lists the indexes of the schema in schema order, automatic indexes included.
+    pub fn indexes(&mut self) -> std::io::Result<Vec<IndexInfo>> {
+        let schema = Schema::read(self)?;
+
+        schema.entries.into_iter()
+            .filter(|entry| entry.typ == SchemaEntryType::Index)
+            .map(IndexInfo::from_entry)
+            .collect()
+    }
+
+    /// Iterates over the rows of the table b-tree rooted at `root_page`.
+    pub fn rows(&mut self, root_page: u32) -> TableIterator<'_, R> {
+        TableIterator::new(&mut self.pager, root_page)
+    }
+
+    /// Iterates over the entries of the index b-tree rooted at `root_page` in key order.
+    /// This also iterates over the rows of a `WITHOUT ROWID` table, in primary key order and
+    /// with the columns in storage order, see [`crate::TableDef::declared_order`].
+    pub fn index_entries(&mut self, root_page: u32) -> IndexIterator<'_, R> {
+        IndexIterator::new(&mut self.pager, root_page)
+    }
+
+    /// Iterates over the b-tree pages of the database in file order, with their page numbers,
+    /// skipping freelist, pointer-map, overflow and other pages that are not b-tree pages.
+    /// See [`PageInfo::read_all`] to classify every page instead.
+    pub fn pages(&mut self) -> PageIterator<'_, R> {
+        PageIterator::new(&mut self.pager)
+    }
+
+    /// Looks up a row of the table b-tree rooted at `root_page` by its rowid, descending
+    /// only through the pages whose key range covers the rowid.
+    pub fn find_row(&mut self, root_page: u32, rowid: i64) -> std::io::Result<Option<Record>> {
+        self.descend_to_row(root_page, rowid, None)
+    }
+
+    /// Looks up a row like [`Database::find_row`], also returning the pages visited on the
+    /// way to it, from the root page down to the leaf page, with the child chosen on each.
+    pub fn find_row_traced(&mut self, root_page: u32, rowid: i64) -> std::io::Result<(Option<Record>, Vec<TraceStep>)> {
+        let mut trace = Vec::new();
+        let record = self.descend_to_row(root_page, rowid, Some(&mut trace))?;
+        Ok((record, trace))
+    }
+
+    fn descend_to_row(&mut self, root_page: u32, rowid: i64, mut trace: Option<&mut Vec<TraceStep>>) -> std::io::Result<Option<Record>> {
+        let mut page_number = root_page;
+
+        for _ in 0..=MAX_BTREE_DEPTH {
+            let page = self.pager.get(page_number)?;
+            let mut record_step = |decision| if let Some(trace) = trace.as_deref_mut() {
+                trace.push(TraceStep {
+                    page_number,
+                    page_type: page.header.typ,
+                    cells_count: page.cells.len(),
+                    decision,
+                });
+            };
+
+            match page.header.typ {
+                TableInterior => {
+                    // Each interior cell holds the largest rowid of its left subtree
+                    let index = page.cells.partition_point(|cell| cell.rowid.is_some_and(|key| key < rowid));
+
+                    let child_page_number = match page.cells.get(index) {
+                        Some(cell) => {
+                            let child_page_number = cell.left_child_page_number
+                                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Interior page {} is missing a child pointer", page_number)))?;
+                            record_step(TraceDecision::LeftChild {
+                                cell_index: index,
+                                key: cell.rowid.unwrap_or_default(),
+                                child_page_number,
+                            });
+                            child_page_number
+                        }
+                        None => {
+                            let child_page_number = page.right_most_pointer()?;
+                            record_step(TraceDecision::RightMostChild {
+                                largest_key: page.cells.last().and_then(|cell| cell.rowid),
+                                child_page_number,
+                            });
+                            child_page_number
+                        }
+                    };
+                    page_number = self.pager.header().check_child_page(page_number, child_page_number)?;
+                }
+                TableLeaf => {
+                    return Ok(match page.cells.binary_search_by_key(&Some(rowid), |cell| cell.rowid) {
+                        Ok(index) => {
+                            record_step(TraceDecision::Found { cell_index: index });
+                            page.cells[index].payload.clone()
+                        }
+                        Err(index) => {
+                            record_step(TraceDecision::NotFound { cell_index: index });
+                            None
+                        }
+                    });
+                }
+                ref x => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for a table b-tree", page_number, x))),
+            }
+        }
+
+        Err(Error::new(ErrorKind::InvalidData, format!("B-tree rooted at page {} is deeper than {} levels", root_page, MAX_BTREE_DEPTH)))
+    }
+
+    /// Opens a reader streaming the blob in column `column` of the row with the given rowid in
+    /// the table b-tree rooted at `root_page`, or returns `None` if there is no such row. The
+    /// column index is that of the record, which is the declared one for rowid tables.
+    ///
+    /// Unlike [`Database::find_row`], neither the row nor the pages on the way to it are decoded:
+    /// only the cells probed by the descent are read, bypassing the cache, and the blob itself is
+    /// read from its overflow pages as the reader is consumed.
+    pub fn blob_reader(&mut self, root_page: u32, rowid: i64, column: usize) -> std::io::Result<Option<BlobReader<'_, R>>> {
+        let mut page_number = root_page;
+
+        for _ in 0..=MAX_BTREE_DEPTH {
+            let pointers = self.pager.cell_pointers(page_number)?;
+            let missing = |what: &str| Error::new(ErrorKind::InvalidData, format!("Page {} has a cell without a {}", page_number, what));
+
+            // Each interior cell holds the largest rowid of its left subtree, and leaf cells are sorted by rowid
+            let (mut low, mut high) = (0, pointers.offsets.len());
+            while low < high {
+                let middle = (low + high) / 2;
+                let key = self.pager.cell_without_payload(&pointers, middle)?.rowid.ok_or_else(|| missing("rowid"))?;
+                if key < rowid {
+                    low = middle + 1;
+                } else {
+                    high = middle;
+                }
+            }
+
+            match pointers.header.typ {
+                TableInterior => {
+                    let child_page_number = if low < pointers.offsets.len() {
+                        self.pager.cell_without_payload(&pointers, low)?.left_child_page_number.ok_or_else(|| missing("child pointer"))?
+                    } else {
+                        pointers.header.right_most_pointer
+                            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Interior page {} has no right-most pointer", page_number)))?
+                    };
+                    page_number = self.pager.header().check_child_page(page_number, child_page_number)?;
+                }
+                TableLeaf => {
+                    if low == pointers.offsets.len() {
+                        return Ok(None);
+                    }
+
+                    let cell = self.pager.cell_without_payload(&pointers, low)?;
+                    if cell.rowid != Some(rowid) {
+                        return Ok(None);
+                    }
+
+                    return BlobReader::new(&mut self.pager, &cell, column).map(Some);
+                }
+                ref x => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for a table b-tree", page_number, x))),
+            }
+        }
+
+        Err(Error::new(ErrorKind::InvalidData, format!("B-tree rooted at page {} is deeper than {} levels", root_page, MAX_BTREE_DEPTH)))
+    }
+
+    /// Looks up several rows of the table b-tree rooted at `root_page` at once, returning the
+    /// ones that exist in rowid order, each only once.
+    ///
+    /// This descends as [`Database::find_row`] does, but with the sorted rowids split among the
+    /// children of each interior page, so that pages shared by the paths to several rows are
+    /// only visited once.
+    pub fn find_rows(&mut self, root_page: u32, rowids: &[i64]) -> std::io::Result<Vec<(i64, Record)>> {
+        let mut rowids = rowids.to_vec();
+        rowids.sort_unstable();
+        rowids.dedup();
+
+        let mut rows = Vec::new();
+        self.find_rows_in_page(root_page, &rowids, 0, &mut rows)?;
+        Ok(rows)
+    }
+
+    fn find_rows_in_page(&mut self, page_number: u32, rowids: &[i64], depth: usize, rows: &mut Vec<(i64, Record)>) -> std::io::Result<()> {
+        // SQLite never builds b-trees this deep, so the pages must form a cycle
+        if depth > MAX_BTREE_DEPTH {
+            return Err(Error::new(ErrorKind::InvalidData, format!("B-tree is deeper than {} levels at page {}", MAX_BTREE_DEPTH, page_number)));
+        }
+
+        let page = self.pager.get(page_number)?;
+
+        match page.header.typ {
+            TableInterior => {
+                let mut rowids = rowids;
+                for cell in page.cells.iter() {
+                    if rowids.is_empty() {
+                        return Ok(());
+                    }
+
+                    let key = cell.rowid.ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Interior page {} has a cell without a rowid", page_number)))?;
+                    let (left, right) = rowids.split_at(rowids.partition_point(|&rowid| rowid <= key));
+                    if !left.is_empty() {
+                        let left_child_page_number = cell.left_child_page_number
+                            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Interior page {} is missing a child pointer", page_number)))?;
+                        self.find_rows_in_page(self.pager.header().check_child_page(page_number, left_child_page_number)?, left, depth + 1, rows)?;
+                    }
+                    rowids = right;
+                }
+
+                if !rowids.is_empty() {
+                    self.find_rows_in_page(self.pager.header().check_child_page(page_number, page.right_most_pointer()?)?, rowids, depth + 1, rows)?;
+                }
+            }
+            TableLeaf => {
+                for &rowid in rowids {
+                    if let Ok(index) = page.cells.binary_search_by_key(&Some(rowid), |cell| cell.rowid) {
+                        if let Some(record) = &page.cells[index].payload {
+                            rows.push((rowid, record.clone()));
+                        }
+                    }
+                }
+            }
+            ref x => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for a table b-tree", page_number, x))),
+        }
+
+        Ok(())
+    }
+
+    /// Searches the index b-tree rooted at `root_page` for entries whose leading columns equal
+    /// `key`, returning the rowids stored in the last column of each match in index order.
+    /// A key shorter than the index matches on its prefix, as with multi-column indexes.
+    ///
+    /// Text is compared with the collations of the index columns, looked up in the schema,
+    /// so that `'alice'` finds `'Alice'` in a `NOCASE` index, and `DESC` columns are compared
+    /// in reverse, as they are stored. Automatic indexes and b-trees not listed in the schema
+    /// use `BINARY` and ascending order, see [`Database::find_index_collated`].
+    pub fn find_index(&mut self, root_page: u32, key: &[RecordEntry]) -> std::io::Result<Vec<i64>> {
+        let schema = Schema::read(self)?;
+        let (collations, descending) = match schema.entry_by_root_page(root_page) {
+            Some(entry) if entry.typ == SchemaEntryType::Index => match (&entry.sql, schema.entry(&entry.table_name).map(|table| table.table_def())) {
+                (Some(sql), Some(Ok(table))) => (table.index_collations(sql)?, descending_columns(sql, self.pager.header().schema_format())),
+                _ => (Vec::new(), Vec::new()),
+            },
+            _ => (Vec::new(), Vec::new()),
+        };
+
+        let mut rowids = Vec::new();
+        self.search_index_page(root_page, key, &collations, &descending, 0, &mut rowids)?;
+        Ok(rowids)
+    }
+
+    /// Searches an index b-tree like [`Database::find_index`], comparing text in each column
+    /// with the collation given for it, or with `BINARY` for the columns past the end of `collations`.
+    /// Every column is expected in ascending order.
+    pub fn find_index_collated(&mut self, root_page: u32, key: &[RecordEntry], collations: &[Collation]) -> std::io::Result<Vec<i64>> {
+        let mut rowids = Vec::new();
+        self.search_index_page(root_page, key, collations, &[], 0, &mut rowids)?;
+        Ok(rowids)
+    }
+
+    fn search_index_page(&mut self, page_number: u32, key: &[RecordEntry], collations: &[Collation], descending: &[bool], depth: usize, rowids: &mut Vec<i64>) -> std::io::Result<()> {
+        // SQLite never builds b-trees this deep, so the pages must form a cycle
+        if depth > MAX_BTREE_DEPTH {
+            return Err(Error::new(ErrorKind::InvalidData, format!("B-tree is deeper than {} levels at page {}", MAX_BTREE_DEPTH, page_number)));
+        }
+
+        let page = self.pager.get(page_number)?;
+        let compare = |record: &Record| compare_prefix_ordered(&record.entries, key, collations, descending);
+
+        let index_rowid = |record: &Record| match record.entries.last() {
+            Some(RecordEntry::Integer(rowid)) => Ok(*rowid),
+            x => Err(Error::new(ErrorKind::InvalidData, format!("Index entry on page {} ends with {:?} instead of a rowid", page_number, x))),
+        };
+
+        let missing_payload = || Error::new(ErrorKind::InvalidData, format!("Index cell on page {} has no payload", page_number));
+
+        match page.header.typ {
+            IndexLeaf => {
+                for cell in page.cells.iter() {
+                    let record = cell.payload.as_ref().ok_or_else(missing_payload)?;
+                    if compare(record) == Ordering::Equal {
+                        rowids.push(index_rowid(record)?);
+                    }
+                }
+            }
+            IndexInterior => {
+                // Cells before the first one not less than the key only lead to smaller keys
+                let start = page.cells.partition_point(|cell| cell.payload.as_ref().is_some_and(|record| compare(record) == Ordering::Less));
+
+                for cell in page.cells[start..].iter() {
+                    let record = cell.payload.as_ref().ok_or_else(missing_payload)?;
+                    let left_child_page_number = cell.left_child_page_number
+                        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Index cell on page {} has no left child pointer", page_number)))?;
+
+                    self.search_index_page(self.pager.header().check_child_page(page_number, left_child_page_number)?, key, collations, descending, depth + 1, rowids)?;
+
+                    if compare(record) == Ordering::Greater {
+                        return Ok(());
+                    }
+
+                    rowids.push(index_rowid(record)?);
+                }
+
+                self.search_index_page(self.pager.header().check_child_page(page_number, page.right_most_pointer()?)?, key, collations, descending, depth + 1, rowids)?;
+            }
+            ref x => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for an index b-tree", page_number, x))),
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks whether an index or `WITHOUT ROWID` table is declared without any `DESC` or `COLLATE`,
+/// so that its entries are in ascending order with the `BINARY` collation.
+fn has_default_order(sql: &str) -> bool {
+    tokenize(sql).is_ok_and(|tokens| !tokens.iter().any(|token| token.is_keyword("DESC") || token.is_keyword("COLLATE")))
+}
+
+/// Marks the columns of an index declared `DESC`, which are stored in descending order.
+/// Databases with a schema format older than 4 ignore `DESC` and store every column in
+/// ascending order.
+fn descending_columns(sql: &str, schema_format: u32) -> Vec<bool> {
+    if schema_format < 4 {
+        return Vec::new();
+    }
+
+    let Ok(tokens) = tokenize(sql) else {
+        return Vec::new();
+    };
+
+    index_column_list(&tokens)
+        .map(|columns| columns.iter().map(|column| column.last().is_some_and(|token| token.is_keyword("DESC"))).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,79 @@
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+
+use crate::{Database, ReadOptions};
+
+/// Collects the options of a [`Database`] before opening it, see [`Database::builder`].
+///
+/// Options that are not set keep their defaults, which are those of [`Database::open`].
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseBuilder {
+    options: ReadOptions,
+    cache_capacity: Option<usize>,
+}
+
+impl DatabaseBuilder {
+    /// Replaces all read options at once.
+    pub fn read_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// See [`ReadOptions::lossy_text`].
+    pub fn lossy_text(mut self, lossy_text: bool) -> Self {
+        self.options.lossy_text = lossy_text;
+        self
+    }
+
+    /// See [`ReadOptions::decode_payload`].
+    pub fn decode_payload(mut self, decode_payload: bool) -> Self {
+        self.options.decode_payload = decode_payload;
+        self
+    }
+
+    /// See [`ReadOptions::unknown_encoding_as_utf8`].
+    pub fn unknown_encoding_as_utf8(mut self, unknown_encoding_as_utf8: bool) -> Self {
+        self.options.unknown_encoding_as_utf8 = unknown_encoding_as_utf8;
+        self
+    }
+
+    /// See [`ReadOptions::keep_raw_payload`].
+    pub fn keep_raw_payload(mut self, keep_raw_payload: bool) -> Self {
+        self.options.keep_raw_payload = keep_raw_payload;
+        self
+    }
+
+    /// Limits the page cache to `capacity` pages, see [`Pager::with_cache_capacity`](crate::Pager::with_cache_capacity).
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Opens a database file like [`Database::open`], with the collected options.
+    pub fn open<P>(&self, path: P) -> std::io::Result<Database<File>>
+        where P: AsRef<Path>
+    {
+        Ok(self.apply(Database::open(path)?))
+    }
+
+    /// Opens a database from any seekable source like [`Database::open_reader`], with the collected options.
+    pub fn open_reader<R>(&self, reader: R) -> std::io::Result<Database<R>>
+        where R: Read + Seek
+    {
+        Ok(self.apply(Database::open_reader(reader)?))
+    }
+
+    /// Opens a database already in memory like [`Database::from_bytes`], with the collected options.
+    pub fn from_bytes<'a>(&self, data: &'a [u8]) -> std::io::Result<Database<Cursor<&'a [u8]>>> {
+        Ok(self.apply(Database::from_bytes(data)?))
+    }
+
+    fn apply<R>(&self, mut database: Database<R>) -> Database<R>
+        where R: Read + Seek
+    {
+        database.set_read_options(self.options);
+        database.pager().set_cache_capacity(self.cache_capacity);
+        database
+    }
+}
@@ -0,0 +1,206 @@
+use std::cmp::Ordering;
+
+use crate::{compare_entries_collated, Affinity, Collation, FilePageCell, Record, RecordEntry, TableDef};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub fn matches(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// Compares a column of a record against a fixed value, like `column <op> value` in a `WHERE` clause.
+#[derive(Debug, Clone)]
+pub struct ColumnPredicate {
+    pub index: usize,
+    pub comparison: Comparison,
+    pub value: RecordEntry,
+    /// Affinity of the column, applied to its values before comparing them, or `None` to
+    /// compare the values as they are stored.
+    pub affinity: Option<Affinity>,
+    /// Collation used to compare text values.
+    pub collation: Collation,
+    /// Value compared in place of the column when a record is too short to have it, as in rows
+    /// written before an `ALTER TABLE ADD COLUMN`, or `None` for the predicate not to match them.
+    pub default: Option<RecordEntry>,
+}
+
+impl ColumnPredicate {
+    /// As in SQL, a comparison involving NULL never matches, and neither does a missing column
+    /// without a default value.
+    pub fn matches(&self, record: &Record) -> bool {
+        match record.entries.get(self.index).or(self.default.as_ref()) {
+            None | Some(RecordEntry::Null) => false,
+            Some(_) if matches!(self.value, RecordEntry::Null) => false,
+            Some(entry) => match self.affinity {
+                Some(affinity) => self.comparison.matches(compare_entries_collated(&affinity.apply(entry), &self.value, self.collation)),
+                None => self.comparison.matches(compare_entries_collated(entry, &self.value, self.collation)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub min_rowid: Option<i64>,
+    pub max_rowid: Option<i64>,
+    pub columns: Vec<ColumnPredicate>,
+}
+
+impl Filter {
+    /// Creates a filter matching records whose column at `index` compares to `value` as given.
+    pub fn column(index: usize, comparison: Comparison, value: RecordEntry) -> Self {
+        Filter::default().and_column(index, comparison, value)
+    }
+
+    /// Adds another column predicate that must hold as well.
+    pub fn and_column(mut self, index: usize, comparison: Comparison, value: RecordEntry) -> Self {
+        self.columns.push(ColumnPredicate {
+            index,
+            comparison,
+            value,
+            affinity: None,
+            collation: Collation::Binary,
+            default: None,
+        });
+        self
+    }
+
+    /// Adds a predicate on the column of `table` with the given declared index, which compares
+    /// like SQLite does: the affinity of the column is applied to `value` and to the values
+    /// of the column, so that `'42'` matches 42 in an `INTEGER` column, and text is compared
+    /// with the collation of the column. Rows written before the column was added compare
+    /// its default value.
+    ///
+    /// A `VIRTUAL` generated column is not stored, so a predicate on it never matches.
+    pub fn and_table_column(mut self, table: &TableDef, index: usize, comparison: Comparison, value: RecordEntry) -> Self {
+        let affinity = table.columns[index].affinity;
+        self.columns.push(ColumnPredicate {
+            index: table.storage_index(index).unwrap_or(usize::MAX),
+            comparison,
+            value: affinity.apply(&value).into_owned(),
+            affinity: Some(affinity),
+            collation: table.columns[index].collation,
+            default: table.columns[index].default_value(),
+        });
+        self
+    }
+
+    /// Checks a leaf cell against the filter. Interior cells always match, since they lead to
+    /// subtrees that may still contain matching rows, see [`Filter::overlaps_rowids`].
+    pub fn matches(&self, cell: &FilePageCell) -> bool {
+        if cell.left_child_page_number.is_some() {
+            return true;
+        }
+
+        let mut result = true;
+
+        result &= match self.min_rowid {
+            Some(min_rowid) => cell.rowid.map(|rowid| rowid >= min_rowid).unwrap_or(false),
+            None => true,
+        };
+
+        result &= match self.max_rowid {
+            Some(max_rowid) => cell.rowid.map(|rowid| rowid <= max_rowid).unwrap_or(false),
+            None => true,
+        };
+
+        result &= match &cell.payload {
+            Some(record) => self.matches_record(record),
+            None => true,
+        };
+
+        result
+    }
+
+    /// Checks whether a subtree of a table b-tree may contain rows within the rowid range.
+    ///
+    /// The key of an interior cell is the largest rowid of its left subtree, so the subtree
+    /// holds the rowids above the key of the previous cell, `after`, up to and including its
+    /// own key, `up_to`. `None` stands for no bound, as for the first and the right-most child.
+    pub fn overlaps_rowids(&self, after: Option<i64>, up_to: Option<i64>) -> bool {
+        let above_min = match (self.min_rowid, up_to) {
+            (Some(min_rowid), Some(up_to)) => up_to >= min_rowid,
+            _ => true,
+        };
+
+        let below_max = match (self.max_rowid, after) {
+            (Some(max_rowid), Some(after)) => after < max_rowid,
+            _ => true,
+        };
+
+        above_min && below_max
+    }
+
+    /// Checks the column predicates against a decoded record.
+    pub fn matches_record(&self, record: &Record) -> bool {
+        self.columns.iter().all(|predicate| predicate.matches(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(n: RecordEntry) -> Record {
+        Record {
+            entries: vec![RecordEntry::Null, n],
+            serial_types: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn numeric_text_compares_as_a_number_in_an_integer_column() {
+        let table = TableDef::parse("CREATE TABLE t(id INTEGER PRIMARY KEY, n INTEGER)").unwrap();
+        let filter = Filter::default().and_table_column(&table, 1, Comparison::Greater, RecordEntry::Integer(10));
+
+        // Written by a tool that did not apply the affinity of the column
+        assert!(filter.matches_record(&record(RecordEntry::Text("42".to_string()))));
+        assert!(filter.matches_record(&record(RecordEntry::Text(" 11 ".to_string()))));
+        assert!(!filter.matches_record(&record(RecordEntry::Text("7".to_string()))));
+        assert!(filter.matches_record(&record(RecordEntry::Integer(42))));
+        assert!(!filter.matches_record(&record(RecordEntry::Integer(7))));
+
+        // Text that is not a number stays text, which is greater than any number
+        assert!(filter.matches_record(&record(RecordEntry::Text("7 apples".to_string()))));
+
+        // Without the affinity, any text is greater than a number
+        let filter = Filter::column(1, Comparison::Greater, RecordEntry::Integer(10));
+        assert!(filter.matches_record(&record(RecordEntry::Text("7".to_string()))));
+    }
+
+    #[test]
+    fn the_value_takes_the_affinity_of_the_column() {
+        let table = TableDef::parse("CREATE TABLE t(id INTEGER PRIMARY KEY, n INTEGER)").unwrap();
+        let filter = Filter::default().and_table_column(&table, 1, Comparison::Greater, RecordEntry::Text("10".to_string()));
+
+        assert!(filter.matches_record(&record(RecordEntry::Integer(42))));
+        assert!(!filter.matches_record(&record(RecordEntry::Integer(7))));
+    }
+
+    #[test]
+    fn large_integers_compare_exactly_with_floats() {
+        let filter = Filter::column(1, Comparison::Greater, RecordEntry::Float(9007199254740992.0));
+        assert!(filter.matches_record(&record(RecordEntry::Integer(9007199254740993))));
+        assert!(!filter.matches_record(&record(RecordEntry::Integer(9007199254740992))));
+
+        let filter = Filter::column(1, Comparison::Equal, RecordEntry::Integer(9007199254740993));
+        assert!(!filter.matches_record(&record(RecordEntry::Float(9007199254740992.0))));
+    }
+}
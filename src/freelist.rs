@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Read, Seek};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{FileHeader, Pager};
+
+/// Pages that are unused by the database, as recorded in its freelist.
+///
+/// The freelist is a chain of trunk pages, each listing a number of leaf pages.
+/// Neither kind of page holds live data, but they may still hold deleted content.
+#[derive(Debug, Default)]
+pub struct Freelist {
+    pub trunk_pages: Vec<u32>,
+    pub leaf_pages: Vec<u32>,
+}
+
+impl Freelist {
+    pub fn read<R>(pager: &mut Pager<R>, header: &FileHeader) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let mut freelist = Freelist::default();
+        let mut visited = HashSet::new();
+        let mut trunk_page = header.first_freelist_trunk_page;
+
+        while trunk_page != 0 {
+            if trunk_page > header.database_size || !visited.insert(trunk_page) {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Invalid freelist trunk page {}", trunk_page)));
+            }
+
+            let data = pager.read_raw(trunk_page)?;
+            let next_trunk_page = BigEndian::read_u32(&data[0..4]);
+            let leaf_count = BigEndian::read_u32(&data[4..8]) as usize;
+
+            if leaf_count > header.usable_size() as usize / 4 - 2 {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Freelist trunk page {} claims {} leaf pages", trunk_page, leaf_count)));
+            }
+
+            freelist.trunk_pages.push(trunk_page);
+            freelist.leaf_pages.extend(data[8..8 + leaf_count * 4].chunks_exact(4).map(BigEndian::read_u32));
+
+            trunk_page = next_trunk_page;
+        }
+
+        Ok(freelist)
+    }
+
+    /// All free pages, trunk and leaf, in ascending order.
+    pub fn pages(&self) -> Vec<u32> {
+        let mut pages = [self.trunk_pages.as_slice(), self.leaf_pages.as_slice()].concat();
+        pages.sort_unstable();
+        pages
+    }
+}
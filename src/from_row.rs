@@ -0,0 +1,121 @@
+use std::io::{Error, ErrorKind};
+
+use crate::{RecordEntry, RecordView};
+
+/// A type that can be built from a row viewed through its table, usually a struct with a
+/// field for each column it needs. Implementations look the columns up by name with
+/// [`RecordView::column`], so that they keep working when columns are added or reordered.
+pub trait FromRow: Sized {
+    fn from_row(row: &RecordView<'_>) -> std::io::Result<Self>;
+}
+
+/// A type that a single entry of a record can be converted into, see [`RecordView::column`].
+///
+/// Conversions are strict, as SQLite has already applied the column affinity when storing
+/// the value: only integers convert to integers, and text is never parsed as a number.
+/// Integers do convert to floating point, as SQLite stores integral floats as integers.
+pub trait FromEntry: Sized {
+    fn from_entry(entry: &RecordEntry) -> Option<Self>;
+}
+
+impl FromEntry for RecordEntry {
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        Some(entry.clone())
+    }
+}
+
+impl FromEntry for i64 {
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        entry.as_i64()
+    }
+}
+
+impl FromEntry for i32 {
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        entry.as_i64().and_then(|value| i32::try_from(value).ok())
+    }
+}
+
+impl FromEntry for u32 {
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        entry.as_i64().and_then(|value| u32::try_from(value).ok())
+    }
+}
+
+/// Booleans are stored as the integers 0 and 1.
+impl FromEntry for bool {
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        match entry.as_i64() {
+            Some(0) => Some(false),
+            Some(1) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl FromEntry for f64 {
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        entry.as_f64()
+    }
+}
+
+impl FromEntry for String {
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        entry.as_str().map(str::to_string)
+    }
+}
+
+impl FromEntry for Vec<u8> {
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        entry.as_blob().map(<[u8]>::to_vec)
+    }
+}
+
+/// NULL converts to `None`, and any other entry to `Some` if it converts to the inner type.
+impl<T> FromEntry for Option<T>
+    where T: FromEntry
+{
+    fn from_entry(entry: &RecordEntry) -> Option<Self> {
+        match entry {
+            RecordEntry::Null => Some(None),
+            entry => T::from_entry(entry).map(Some),
+        }
+    }
+}
+
+impl RecordView<'_> {
+    /// Converts the entry of the column with the given name. A column missing from the record,
+    /// as in rows written before an `ALTER TABLE ADD COLUMN`, reads as its default value, see
+    /// [`ColumnDef::default_value`](crate::ColumnDef::default_value), or NULL if it has none.
+    pub fn column<T>(&self, name: &str) -> std::io::Result<T>
+        where T: FromEntry
+    {
+        let index = self.table.column_index(name)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Table {} has no column {}", self.table.name, name)))?;
+        let default;
+        let entry = match self.record.get(index) {
+            Some(entry) => entry,
+            None => {
+                default = self.table.columns[index].default_value().unwrap_or(RecordEntry::Null);
+                &default
+            }
+        };
+
+        let storage_class = match entry {
+            RecordEntry::Null => "NULL",
+            RecordEntry::Integer(_) => "an integer",
+            RecordEntry::Float(_) => "a float",
+            RecordEntry::Blob(_) => "a blob",
+            RecordEntry::Text(_) => "text",
+        };
+
+        T::from_entry(entry).ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Column {} of table {} holds {}, which does not convert to {}", name, self.table.name, storage_class, std::any::type_name::<T>())))
+    }
+
+    /// Builds a value of type `T` from this row, see [`FromRow`].
+    pub fn parse<T>(&self) -> std::io::Result<T>
+        where T: FromRow
+    {
+        T::from_row(self)
+    }
+}
@@ -0,0 +1,391 @@
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// Offset of the page SQLite uses for file locking, which never holds any data.
+const LOCK_BYTE_OFFSET: u64 = 0x40000000;
+
+/// Where the number of pages reported by [`FileHeader::database_size`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DatabaseSizeSource {
+    /// The in-header database size, which is valid and was trusted.
+    Header,
+    /// The length of the file divided by the page size, used when the in-header
+    /// database size is zero or stale.
+    FileLength,
+    /// The database size recorded by the last commit in the write-ahead log.
+    Wal,
+}
+
+/// How unused pages are reclaimed, see [`FileHeader::vacuum_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum VacuumMode {
+    /// Free pages stay in the freelist until the database is vacuumed.
+    None,
+    /// Free pages are moved to the end of the file and truncated on every commit.
+    Full,
+    /// Free pages are only truncated when `PRAGMA incremental_vacuum` is run.
+    Incremental,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileHeader {
+    pub(crate) page_size: u32,
+    pub(crate) write_version: u8,
+    pub(crate) read_version: u8,
+    pub(crate) reserved_space: u8,
+    pub(crate) change_counter: u32,
+    pub(crate) database_size: u32,
+    pub(crate) database_size_source: DatabaseSizeSource,
+    pub(crate) first_freelist_trunk_page: u32,
+    pub(crate) freelist_page_count: u32,
+    pub(crate) schema_format: u32,
+    pub(crate) largest_root_page: u32,
+    pub(crate) text_encoding: u32,
+    pub(crate) user_version: u32,
+    pub(crate) incremental_vacuum: bool,
+    pub(crate) application_id: u32,
+    pub(crate) version_valid_for: u32,
+}
+
+impl FileHeader {
+    pub fn read<R>(reader: &mut R) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let mut magic = [0; 16];
+        reader.seek(SeekFrom::Start(0))?;
+
+        match reader.read_exact(&mut magic) {
+            Ok(()) if &magic == MAGIC => {}
+            Ok(()) => return Err(Error::new(ErrorKind::InvalidData, "not a SQLite database")),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Err(Error::new(ErrorKind::InvalidData, "not a SQLite database")),
+            Err(err) => return Err(err),
+        }
+
+        // A page size of 65536 does not fit into two bytes and is stored as 1 instead
+        let page_size = match reader.read_u16::<BigEndian>()? {
+            1 => 65536,
+            x => x as u32,
+        };
+
+        if !page_size.is_power_of_two() || !(512..=65536).contains(&page_size) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Invalid page size: {}", page_size)));
+        }
+
+        let write_version = reader.read_u8()?;
+        let read_version = reader.read_u8()?;
+        let reserved_space = reader.read_u8()?;
+
+        // SQLite requires at least 480 usable bytes per page
+        if page_size - (reserved_space as u32) < 480 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Invalid reserved space: {} bytes of a {} byte page", reserved_space, page_size)));
+        }
+
+        reader.seek(SeekFrom::Start(24))?;
+        let change_counter = reader.read_u32::<BigEndian>()?;
+        let database_size = reader.read_u32::<BigEndian>()?;
+        let first_freelist_trunk_page = reader.read_u32::<BigEndian>()?;
+        let freelist_page_count = reader.read_u32::<BigEndian>()?;
+
+        reader.seek(SeekFrom::Start(44))?;
+        let schema_format = reader.read_u32::<BigEndian>()?;
+
+        reader.seek(SeekFrom::Start(52))?;
+        let largest_root_page = reader.read_u32::<BigEndian>()?;
+        let text_encoding = reader.read_u32::<BigEndian>()?;
+        let user_version = reader.read_u32::<BigEndian>()?;
+        let incremental_vacuum = reader.read_u32::<BigEndian>()? != 0;
+        let application_id = reader.read_u32::<BigEndian>()?;
+
+        reader.seek(SeekFrom::Start(92))?;
+        let version_valid_for = reader.read_u32::<BigEndian>()?;
+
+        // The in-header size is only valid if no legacy writer modified the file since it was
+        // last set, which is indicated by the change counter matching the version-valid-for number
+        let (database_size, database_size_source) = if database_size != 0 && change_counter == version_valid_for {
+            (database_size, DatabaseSizeSource::Header)
+        } else {
+            let file_length = reader.seek(SeekFrom::End(0))?;
+            let database_size = u32::try_from(file_length / page_size as u64)
+                .map_err(|_| Error::new(ErrorKind::InvalidData, format!("File is too large: {} bytes", file_length)))?;
+            (database_size, DatabaseSizeSource::FileLength)
+        };
+
+        reader.seek(SeekFrom::Start(100))?;
+
+        Ok(FileHeader {
+            page_size,
+            write_version,
+            read_version,
+            reserved_space,
+            change_counter,
+            database_size,
+            database_size_source,
+            first_freelist_trunk_page,
+            freelist_page_count,
+            schema_format,
+            largest_root_page,
+            text_encoding,
+            user_version,
+            incremental_vacuum,
+            application_id,
+            version_valid_for,
+        })
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// File format write version: 1 for rollback journal mode, 2 for WAL mode.
+    pub fn write_version(&self) -> u8 {
+        self.write_version
+    }
+
+    /// File format read version: 1 for rollback journal mode, 2 for WAL mode.
+    pub fn read_version(&self) -> u8 {
+        self.read_version
+    }
+
+    /// Whether the database was last opened in WAL mode, and may thus have a `-wal` file.
+    pub fn wal_mode(&self) -> bool {
+        self.read_version == 2
+    }
+
+    /// Number of bytes at the end of each page reserved for extensions, such as encryption
+    /// or checksums.
+    pub fn reserved_space(&self) -> u8 {
+        self.reserved_space
+    }
+
+    /// Number of bytes of each page available for b-tree content, excluding the reserved space.
+    pub fn usable_size(&self) -> u32 {
+        self.page_size - self.reserved_space as u32
+    }
+
+    /// Offset in the file of the page with the given 1-based number. The page numbers are
+    /// 32-bit, but the offsets are 64-bit, so that every page of the largest possible file,
+    /// `u32::MAX` pages of 64 KiB, can be addressed without wrapping around.
+    pub fn page_offset(&self, page_number: u32) -> u64 {
+        self.page_size as u64 * (page_number as u64).saturating_sub(1)
+    }
+
+    /// Number of times the database was changed in rollback journal mode. WAL mode writers
+    /// leave it as it is.
+    pub fn change_counter(&self) -> u32 {
+        self.change_counter
+    }
+
+    /// Value of the change counter when the in-header database size was last set.
+    pub fn version_valid_for(&self) -> u32 {
+        self.version_valid_for
+    }
+
+    /// Whether the in-header database size is up to date. Writers that predate it change the
+    /// file without updating the version-valid-for number, so the two numbers no longer match.
+    pub fn header_size_is_valid(&self) -> bool {
+        self.change_counter == self.version_valid_for
+    }
+
+    /// Number of pages in the database.
+    pub fn database_size(&self) -> u32 {
+        self.database_size
+    }
+
+    /// Whether [`FileHeader::database_size`] was taken from the header or computed from the file length.
+    pub fn database_size_source(&self) -> DatabaseSizeSource {
+        self.database_size_source
+    }
+
+    /// Page number of the first freelist trunk page, or 0 if the freelist is empty.
+    pub fn first_freelist_trunk_page(&self) -> u32 {
+        self.first_freelist_trunk_page
+    }
+
+    /// Total number of freelist trunk and leaf pages.
+    pub fn freelist_page_count(&self) -> u32 {
+        self.freelist_page_count
+    }
+
+    /// Schema format number, from 1 to 4. Newer formats add features such as descending
+    /// indexes (4) that older readers do not understand.
+    pub fn schema_format(&self) -> u32 {
+        self.schema_format
+    }
+
+    /// Page number of the largest root b-tree page in auto-vacuum databases, or 0 otherwise.
+    pub fn largest_root_page(&self) -> u32 {
+        self.largest_root_page
+    }
+
+    /// Whether the database is in auto-vacuum or incremental-vacuum mode, and thus has
+    /// pointer-map pages.
+    pub fn is_autovacuum(&self) -> bool {
+        self.largest_root_page != 0
+    }
+
+    /// Whether unused pages are reclaimed on every commit, only on request, or not at all.
+    /// The incremental-vacuum flag at offset 64 only matters for auto-vacuum databases.
+    pub fn vacuum_mode(&self) -> VacuumMode {
+        match (self.is_autovacuum(), self.incremental_vacuum) {
+            (false, _) => VacuumMode::None,
+            (true, false) => VacuumMode::Full,
+            (true, true) => VacuumMode::Incremental,
+        }
+    }
+
+    /// Checks whether the page with the given number is a pointer-map page rather than a
+    /// b-tree, overflow or freelist page.
+    ///
+    /// The first pointer-map page is page 2, and each is followed by the pages it has
+    /// entries for, one 5-byte entry each.
+    pub fn is_ptrmap_page(&self, page_number: u32) -> bool {
+        self.is_autovacuum() && page_number >= 2 && self.ptrmap_page_of(page_number) == page_number
+    }
+
+    /// Page number of the pointer-map page that has the entry of the given page, from page 2 on.
+    /// As in SQLite, a pointer-map page that would fall on the lock-byte page comes right after it.
+    pub(crate) fn ptrmap_page_of(&self, page_number: u32) -> u32 {
+        let stride = self.ptrmap_entries_per_page() + 1;
+        let ptrmap_page = 2 + (page_number - 2) / stride * stride;
+
+        if self.is_lock_byte_page(ptrmap_page) {
+            ptrmap_page + 1
+        } else {
+            ptrmap_page
+        }
+    }
+
+    /// Checks whether the page with the given number holds the lock bytes at offset 2^30,
+    /// which SQLite leaves unused in databases that are large enough to reach it.
+    pub(crate) fn is_lock_byte_page(&self, page_number: u32) -> bool {
+        page_number as u64 == LOCK_BYTE_OFFSET / self.page_size as u64 + 1
+    }
+
+    /// Number of entries on a pointer-map page.
+    pub(crate) fn ptrmap_entries_per_page(&self) -> u32 {
+        self.usable_size() / 5
+    }
+
+    /// Checks that a child pointer of the given b-tree page refers to a page of the database,
+    /// so that traversals never descend into pages that do not exist.
+    pub fn check_child_page(&self, page_number: u32, child_page_number: u32) -> std::io::Result<u32> {
+        if child_page_number == 0 || child_page_number > self.database_size {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Page {} points to page {}, which is outside of 1..={}", page_number, child_page_number, self.database_size)));
+        }
+
+        Ok(child_page_number)
+    }
+
+    /// Text encoding of the database: 1 for UTF-8, 2 for UTF-16le, 3 for UTF-16be.
+    pub fn text_encoding(&self) -> u32 {
+        self.text_encoding
+    }
+
+    /// Version number set by the application through `PRAGMA user_version`.
+    pub fn user_version(&self) -> u32 {
+        self.user_version
+    }
+
+    /// Identifier of the file format built on top of SQLite, set through `PRAGMA application_id`,
+    /// such as `0x47504b47` ("GPKG") for GeoPackage files, or 0 if none is set.
+    pub fn application_id(&self) -> u32 {
+        self.application_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Builds a database header with the given page size and reserved space, and the given
+    /// big-endian `u32` fields at their offsets.
+    fn header(page_size: u16, reserved_space: u8, fields: &[(usize, u32)]) -> Vec<u8> {
+        let mut data = vec![0; 100];
+        data[..16].copy_from_slice(MAGIC);
+        data[16..18].copy_from_slice(&page_size.to_be_bytes());
+        data[18] = 1;
+        data[19] = 1;
+        data[20] = reserved_space;
+        for &(offset, value) in fields {
+            data[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn rejects_files_without_the_magic_string() {
+        let mut data = header(4096, 0, &[]);
+        data[..16].copy_from_slice(b"Not a database\n\0");
+
+        let err = FileHeader::read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "not a SQLite database");
+    }
+
+    #[test]
+    fn rejects_files_shorter_than_the_magic_string() {
+        let err = FileHeader::read(&mut Cursor::new(b"SQLite".to_vec())).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "not a SQLite database");
+    }
+
+    #[test]
+    fn page_size_of_one_means_65536() {
+        let header = FileHeader::read(&mut Cursor::new(header(1, 0, &[]))).unwrap();
+        assert_eq!(header.page_size(), 65536);
+        assert_eq!(header.usable_size(), 65536);
+        assert_eq!(header.page_offset(3), 2 * 65536);
+    }
+
+    #[test]
+    fn rejects_page_sizes_that_are_not_powers_of_two() {
+        let err = FileHeader::read(&mut Cursor::new(header(1000, 0, &[]))).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Invalid page size: 1000");
+    }
+
+    #[test]
+    fn ptrmap_pages_skip_the_lock_byte_page() {
+        // With 774 usable bytes, a pointer-map page has 154 entries and would land on the lock-byte page 1048577
+        let data = header(1024, 250, &[(52, 1)]);
+        let header = FileHeader::read(&mut Cursor::new(data)).unwrap();
+
+        assert!(header.is_autovacuum());
+        assert!(header.is_lock_byte_page(1048577));
+        assert!(header.is_ptrmap_page(2));
+        assert!(header.is_ptrmap_page(1048577 - 155));
+        assert!(!header.is_ptrmap_page(1048577));
+        assert!(header.is_ptrmap_page(1048578));
+        assert_eq!(header.ptrmap_page_of(1048579), 1048578);
+        assert_eq!(header.ptrmap_page_of(1048577 + 154), 1048578);
+        assert!(header.is_ptrmap_page(1048577 + 155));
+    }
+
+    #[test]
+    fn page_numbers_near_u32_max() {
+        let largest = FileHeader::read(&mut Cursor::new(header(1, 0, &[(28, u32::MAX)]))).unwrap();
+        assert_eq!(largest.database_size(), u32::MAX);
+
+        // The largest possible file, of u32::MAX pages of 64 KiB, ends 64 KiB short of 2^48 bytes
+        assert_eq!(largest.page_offset(u32::MAX), (1 << 48) - 2 * 65536);
+        assert_eq!(largest.page_offset(u32::MAX - 1), (1 << 48) - 3 * 65536);
+        assert_eq!(largest.page_offset(1), 0);
+
+        assert_eq!(largest.check_child_page(2, u32::MAX).unwrap(), u32::MAX);
+        let err = largest.check_child_page(2, 0).unwrap_err();
+        assert_eq!(err.to_string(), "Page 2 points to page 0, which is outside of 1..=4294967295");
+
+        let smaller = FileHeader::read(&mut Cursor::new(header(1, 0, &[(28, u32::MAX - 1)]))).unwrap();
+        let err = smaller.check_child_page(7, u32::MAX).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Page 7 points to page 4294967295, which is outside of 1..=4294967294");
+    }
+}
@@ -0,0 +1,111 @@
+use std::io::{Error, ErrorKind, Read, Seek};
+use std::rc::Rc;
+
+use crate::{FilePage, Pager, Record};
+use crate::database::MAX_BTREE_DEPTH;
+use crate::FilePageType::{IndexInterior, IndexLeaf};
+
+/// Iterates over the entries of an index b-tree in key order.
+///
+/// Unlike in table b-trees, the cells of interior index pages hold entries too, each of
+/// which comes after the entries of its left subtree. This is also how the rows of
+/// `WITHOUT ROWID` tables are stored.
+pub struct IndexIterator<'a, R> {
+    pager: &'a mut Pager<R>,
+    /// Pages on the path to the current leaf, each with the index of its next cell and
+    /// whether the left subtree of that cell was already visited.
+    stack: Vec<(Rc<FilePage>, usize, bool)>,
+    /// Page to descend into on the next step.
+    next_page: Option<u32>,
+    root_page: u32,
+    /// Pages read so far, which cannot exceed the size of the database unless the pages form a cycle.
+    pages_visited: u32,
+}
+
+impl<'a, R> IndexIterator<'a, R>
+    where R: Read + Seek
+{
+    pub fn new(pager: &'a mut Pager<R>, root_page: u32) -> Self {
+        IndexIterator {
+            pager,
+            stack: Vec::new(),
+            next_page: Some(root_page),
+            root_page,
+            pages_visited: 0,
+        }
+    }
+
+    fn advance(&mut self) -> std::io::Result<Option<Record>> {
+        loop {
+            if let Some(page_number) = self.next_page.take() {
+                self.pages_visited += 1;
+                if self.pages_visited > self.pager.header().database_size() {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Index b-tree rooted at page {} has more pages than the database", self.root_page)));
+                }
+
+                // SQLite never builds b-trees this deep, so the pages must form a cycle
+                if self.stack.len() >= MAX_BTREE_DEPTH {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("B-tree is deeper than {} levels at page {}", MAX_BTREE_DEPTH, page_number)));
+                }
+
+                let page = self.pager.get(page_number)?;
+
+                match page.header.typ {
+                    IndexInterior | IndexLeaf => self.stack.push((page, 0, false)),
+                    ref x => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for an index b-tree", page_number, x))),
+                }
+            }
+
+            let Some((page, index, visited_left)) = self.stack.last_mut() else {
+                return Ok(None);
+            };
+
+            let cell_index = *index;
+            let missing_payload = || Error::new(ErrorKind::InvalidData, format!("Index cell {} has no payload", cell_index));
+
+            match (&page.header.typ, page.cells.get(cell_index)) {
+                (IndexLeaf, Some(cell)) => {
+                    *index += 1;
+                    return cell.payload.clone().ok_or_else(missing_payload).map(Some);
+                }
+                (IndexInterior, Some(cell)) if !*visited_left => {
+                    *visited_left = true;
+                    let left_child_page_number = cell.left_child_page_number
+                        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Index interior cell {} has no left child pointer", cell_index)))?;
+                    self.next_page = Some(self.pager.header().check_child_page(page.page_number, left_child_page_number)?);
+                }
+                (IndexInterior, Some(cell)) => {
+                    *index += 1;
+                    *visited_left = false;
+                    return cell.payload.clone().ok_or_else(missing_payload).map(Some);
+                }
+                (IndexInterior, None) => {
+                    // The right-most child is the last one visited, so the page is no longer needed
+                    let (page, _, _) = self.stack.pop().unwrap();
+                    self.next_page = Some(self.pager.header().check_child_page(page.page_number, page.right_most_pointer()?)?);
+                }
+                _ => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<R> Iterator for IndexIterator<'_, R>
+    where R: Read + Seek
+{
+    type Item = std::io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.advance() {
+            Ok(entry) => entry.map(Ok),
+            Err(err) => {
+                // Stop after the first error rather than yielding a partial traversal
+                self.stack.clear();
+                self.next_page = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::io::{Read, Seek};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{compare_records, FileHeader, Freelist, Pager, Record};
+use crate::FilePageType::{IndexInterior, TableInterior, TableLeaf};
+
+/// A structural problem found by [`check_integrity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityProblem {
+    /// The page the problem was found on.
+    pub page_number: u32,
+    /// Description of the problem, naming the page.
+    pub message: String,
+}
+
+/// Walks the b-trees rooted at `root_pages`, their overflow chains and the freelist, and
+/// reports every structural problem found instead of stopping at the first one.
+///
+/// A page that cannot be read is reported once and its subtree is skipped. Pages that are
+/// referenced more than once, or from both a b-tree and the freelist, are reported too, and
+/// so are pages that are not used at all, so `root_pages` should list every b-tree of the
+/// database. The rowids of table b-trees must be strictly ascending across all their pages.
+///
+/// The order of index keys depends on the collations and sort orders of the index, which
+/// are not known here, so it is only checked for the trees listed in `ordered_indexes`, see
+/// [`Database::check_integrity`](crate::Database::check_integrity).
+pub fn check_integrity<R>(pager: &mut Pager<R>, root_pages: &[u32], ordered_indexes: &[u32]) -> Vec<IntegrityProblem>
+    where R: Read + Seek
+{
+    let header = pager.header().clone();
+    let mut checker = Checker {
+        pager,
+        header,
+        visited: HashSet::new(),
+        problems: Vec::new(),
+    };
+
+    for &root_page in root_pages {
+        checker.check_tree(root_page, None, KeyRange::default(), ordered_indexes.contains(&root_page));
+    }
+
+    checker.check_freelist();
+    checker.check_unused_pages();
+    checker.problems
+}
+
+struct Checker<'a, R> {
+    pager: &'a mut Pager<R>,
+    header: FileHeader,
+    visited: HashSet<u32>,
+    problems: Vec<IntegrityProblem>,
+}
+
+/// Keys a subtree may hold, as given by the cells of its ancestors on either side of it.
+///
+/// Keys of a table b-tree are above `after_rowid` and up to `up_to_rowid`, as each interior
+/// cell holds the largest rowid of its left subtree. Entries of an index b-tree are strictly
+/// between `after_key` and `before_key`, since the interior cells hold entries of their own.
+#[derive(Clone, Copy, Default)]
+struct KeyRange<'a> {
+    after_rowid: Option<i64>,
+    up_to_rowid: Option<i64>,
+    after_key: Option<&'a Record>,
+    before_key: Option<&'a Record>,
+}
+
+impl KeyRange<'_> {
+    fn contains_rowid(&self, rowid: i64) -> bool {
+        self.after_rowid.is_none_or(|after| rowid > after) && self.up_to_rowid.is_none_or(|up_to| rowid <= up_to)
+    }
+
+    fn contains_key(&self, key: &Record) -> bool {
+        self.after_key.is_none_or(|after| compare_records(key, after) == Ordering::Greater)
+            && self.before_key.is_none_or(|before| compare_records(key, before) == Ordering::Less)
+    }
+}
+
+impl<'a, R> Checker<'a, R>
+    where R: Read + Seek
+{
+    fn report(&mut self, page_number: u32, message: String) {
+        self.problems.push(IntegrityProblem {
+            page_number,
+            message,
+        });
+    }
+
+    /// Marks a page as used, reporting it if it is out of range or was already used.
+    fn visit(&mut self, page_number: u32, referenced_from: u32) -> bool {
+        if page_number == 0 || page_number > self.header.database_size {
+            self.report(referenced_from, format!("Page {} references page {}, which is outside of 1..={}", referenced_from, page_number, self.header.database_size));
+            return false;
+        }
+
+        if self.header.is_ptrmap_page(page_number) {
+            self.report(referenced_from, format!("Page {} references page {}, which is a pointer-map page", referenced_from, page_number));
+            return false;
+        }
+
+        if !self.visited.insert(page_number) {
+            self.report(referenced_from, format!("Page {} references page {}, which is already in use", referenced_from, page_number));
+            return false;
+        }
+
+        true
+    }
+
+    /// Checks the b-tree page and its subtree. `parent` is the page referencing it and whether
+    /// that page belongs to a table b-tree, which the page must match. The keys of the page must
+    /// be within `range`, and the entries of an index page in order if `ordered` is set.
+    fn check_tree(&mut self, page_number: u32, parent: Option<(u32, bool)>, range: KeyRange<'_>, ordered: bool) {
+        if !self.visit(page_number, parent.map_or(page_number, |(parent, _)| parent)) {
+            return;
+        }
+
+        let page = match self.pager.get(page_number) {
+            Ok(page) => page,
+            Err(err) => return self.report(page_number, format!("Page {} could not be read: {}", page_number, err)),
+        };
+
+        let is_table = matches!(page.header.typ, TableInterior | TableLeaf);
+        if let Some((parent, parent_is_table)) = parent {
+            if is_table != parent_is_table {
+                self.report(page_number, format!("Page {} has type {:?}, which does not match the b-tree of parent page {}", page_number, page.header.typ, parent));
+            }
+        }
+
+        for cell in page.cells.iter() {
+            if let Some(overflow_page_number) = cell.first_overflow_page_number {
+                self.check_overflow_chain(overflow_page_number, page_number);
+            }
+        }
+
+        // Lookups rely on the keys being in ascending order, both on the page and across pages
+        let parent_page_number = parent.map_or(page_number, |(parent, _)| parent);
+        if is_table {
+            let rowids = page.cells.iter().filter_map(|cell| cell.rowid).collect::<Vec<_>>();
+            if rowids.windows(2).any(|pair| pair[0] >= pair[1]) {
+                self.report(page_number, format!("Rowids on page {} are not in ascending order", page_number));
+            }
+            if let Some(rowid) = rowids.iter().find(|&&rowid| !range.contains_rowid(rowid)) {
+                self.report(page_number, format!("Rowid {} on page {} is outside of the key range of its parent page {}", rowid, page_number, parent_page_number));
+            }
+        } else if ordered {
+            let keys = page.cells.iter().filter_map(|cell| cell.payload.as_ref()).collect::<Vec<_>>();
+            if keys.windows(2).any(|pair| compare_records(pair[0], pair[1]) != Ordering::Less) {
+                self.report(page_number, format!("Index entries on page {} are not in ascending order", page_number));
+            }
+            if let Some(index) = keys.iter().position(|key| !range.contains_key(key)) {
+                self.report(page_number, format!("Index entry {} on page {} is outside of the key range of its parent page {}", index, page_number, parent_page_number));
+            }
+        }
+
+        if let TableInterior | IndexInterior = page.header.typ {
+            let mut child_range = range;
+            for cell in page.cells.iter() {
+                child_range.up_to_rowid = cell.rowid.or(range.up_to_rowid);
+                child_range.before_key = cell.payload.as_ref().or(range.before_key);
+
+                if let Some(child) = cell.left_child_page_number {
+                    self.check_tree(child, Some((page_number, is_table)), child_range, ordered);
+                }
+
+                child_range.after_rowid = child_range.up_to_rowid;
+                child_range.after_key = child_range.before_key;
+            }
+
+            child_range.up_to_rowid = range.up_to_rowid;
+            child_range.before_key = range.before_key;
+
+            if let Some(child) = page.header.right_most_pointer {
+                self.check_tree(child, Some((page_number, is_table)), child_range, ordered);
+            }
+        }
+    }
+
+    fn check_overflow_chain(&mut self, first_page_number: u32, referenced_from: u32) {
+        let mut page_number = first_page_number;
+        let mut previous = referenced_from;
+
+        while page_number != 0 {
+            if !self.visit(page_number, previous) {
+                return;
+            }
+
+            match self.pager.read_raw(page_number) {
+                Ok(data) => {
+                    previous = page_number;
+                    page_number = BigEndian::read_u32(&data[0..4]);
+                }
+                Err(err) => return self.report(page_number, format!("Page {} could not be read: {}", page_number, err)),
+            }
+        }
+    }
+
+    fn check_freelist(&mut self) {
+        let freelist = match Freelist::read(self.pager, &self.header) {
+            Ok(freelist) => freelist,
+            Err(err) => return self.report(self.header.first_freelist_trunk_page, format!("Freelist could not be read: {}", err)),
+        };
+
+        let pages = freelist.pages();
+        if pages.len() != self.header.freelist_page_count as usize {
+            self.report(1, format!("Freelist has {} pages, but the header claims {}", pages.len(), self.header.freelist_page_count));
+        }
+
+        for page_number in pages {
+            self.visit(page_number, self.header.first_freelist_trunk_page);
+        }
+    }
+
+    /// Reports the pages that are neither part of a b-tree, an overflow chain or the freelist.
+    /// Pointer-map pages and the lock-byte page are used by SQLite itself.
+    fn check_unused_pages(&mut self) {
+        for page_number in 1..=self.header.database_size {
+            if !self.visited.contains(&page_number) && !self.header.is_ptrmap_page(page_number) && !self.header.is_lock_byte_page(page_number) {
+                self.report(page_number, format!("Page {} is never used", page_number));
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::wal::ReadSeek;
+
+const JOURNAL_MAGIC: [u8; 8] = [0xd9, 0xd5, 0x05, 0xf9, 0x20, 0xa1, 0x63, 0xd7];
+const JOURNAL_HEADER_SIZE: usize = 28;
+
+/// A rollback journal holding the original content of pages changed by a transaction.
+///
+/// A journal is hot if it has a valid header, which means that a transaction was
+/// interrupted before it finished: the main file may then contain pages of that transaction,
+/// and is only consistent again once the original pages from the journal are written back.
+/// A journal whose header was zeroed or truncated belongs to a finished transaction.
+pub struct Journal {
+    reader: Box<dyn ReadSeek>,
+    hot: bool,
+    page_size: u32,
+    /// Offset of the original content of each page, from the first record of that page.
+    pages: HashMap<u32, u64>,
+    /// Size of the database in pages before the transaction started.
+    initial_database_size: u32,
+}
+
+impl Journal {
+    pub fn open<P>(path: P) -> std::io::Result<Self>
+        where P: AsRef<Path>
+    {
+        Journal::from_reader(File::open(path)?)
+    }
+
+    pub fn from_reader<J>(mut reader: J) -> std::io::Result<Self>
+        where J: Read + Seek + 'static
+    {
+        let journal_length = reader.seek(SeekFrom::End(0))?;
+        let mut header = [0; JOURNAL_HEADER_SIZE];
+        reader.seek(SeekFrom::Start(0))?;
+
+        let hot = match reader.read_exact(&mut header) {
+            Ok(()) => header[0..8] == JOURNAL_MAGIC,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => false,
+            Err(err) => return Err(err),
+        };
+
+        let mut journal = Journal {
+            reader: Box::new(reader),
+            hot,
+            page_size: 0,
+            pages: HashMap::new(),
+            initial_database_size: 0,
+        };
+
+        if !hot {
+            return Ok(journal);
+        }
+
+        // The page size and sector size are only recorded in the first header
+        let sector_size = BigEndian::read_u32(&header[20..24]) as u64;
+        let page_size = BigEndian::read_u32(&header[24..28]);
+
+        // Like SQLite, a journal with an invalid page size or sector size is not hot, and its
+        // content is never rolled back
+        if !page_size.is_power_of_two() || !(512..=65536).contains(&page_size)
+            || !sector_size.is_power_of_two() || !(32..=65536).contains(&sector_size)
+        {
+            journal.hot = false;
+            return Ok(journal);
+        }
+
+        journal.page_size = page_size;
+        journal.initial_database_size = BigEndian::read_u32(&header[16..20]);
+
+        let record_size = 4 + page_size as u64 + 4;
+        let mut record = vec![0; record_size as usize];
+        let mut header_offset = 0;
+
+        // A journal may consist of several segments, each starting with a header padded to the sector size
+        loop {
+            let nonce = BigEndian::read_u32(&header[12..16]);
+            let records_start = header_offset + sector_size;
+
+            // A record count of -1 means that the records extend up to the end of the journal
+            let record_count = match BigEndian::read_u32(&header[8..12]) {
+                0xffffffff => journal_length.saturating_sub(records_start) / record_size,
+                x => x as u64,
+            };
+
+            journal.reader.seek(SeekFrom::Start(records_start))?;
+
+            for index in 0..record_count {
+                match journal.reader.read_exact(&mut record) {
+                    Ok(()) => {}
+                    Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(journal),
+                    Err(err) => return Err(err),
+                }
+
+                let page_number = BigEndian::read_u32(&record[0..4]);
+                let data = &record[4..4 + page_size as usize];
+                let checksum = BigEndian::read_u32(&record[4 + page_size as usize..]);
+
+                // Records after the first one that fails its checksum were never fully written
+                if page_number == 0 || checksum != journal_checksum(data, nonce) {
+                    return Ok(journal);
+                }
+
+                // Only the first record of a page holds its content before the transaction
+                journal.pages.entry(page_number).or_insert(records_start + index * record_size + 4);
+            }
+
+            header_offset = (records_start + record_count * record_size).div_ceil(sector_size) * sector_size;
+            journal.reader.seek(SeekFrom::Start(header_offset))?;
+
+            match journal.reader.read_exact(&mut header) {
+                Ok(()) if header[0..8] == JOURNAL_MAGIC => {}
+                Ok(()) => break,
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(journal)
+    }
+
+    /// Whether the journal belongs to an unfinished transaction.
+    pub fn is_hot(&self) -> bool {
+        self.hot
+    }
+
+    /// Page size recorded in the journal header, or 0 if the journal is not hot.
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// Size of the database in pages before the unfinished transaction started.
+    pub fn initial_database_size(&self) -> u32 {
+        self.initial_database_size
+    }
+
+    /// Numbers of the pages changed by the unfinished transaction, in ascending order.
+    pub fn dirty_pages(&self) -> Vec<u32> {
+        let mut pages = self.pages.keys().copied().collect::<Vec<_>>();
+        pages.sort_unstable();
+        pages
+    }
+
+    /// Reads the content of a page before the unfinished transaction started.
+    /// Returns `None` if the page was not changed by it.
+    pub fn original_page(&mut self, page_number: u32) -> std::io::Result<Option<Vec<u8>>> {
+        let Some(&offset) = self.pages.get(&page_number) else {
+            return Ok(None);
+        };
+
+        let mut data = vec![0; self.page_size as usize];
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some(data))
+    }
+}
+
+impl fmt::Debug for Journal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Journal")
+            .field("hot", &self.hot)
+            .field("page_size", &self.page_size)
+            .field("page_count", &self.pages.len())
+            .field("initial_database_size", &self.initial_database_size)
+            .finish()
+    }
+}
+
+/// Computes the checksum of a journal record, which only samples every 200th byte of the page.
+fn journal_checksum(data: &[u8], nonce: u32) -> u32 {
+    let mut checksum = nonce;
+    let mut offset = data.len() as isize - 200;
+
+    while offset > 0 {
+        checksum = checksum.wrapping_add(data[offset as usize] as u32);
+        offset -= 200;
+    }
+
+    checksum
+}
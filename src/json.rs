@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use crate::{Record, RecordEntry, Row, TableDef};
+
+/// Largest integer magnitude that JSON consumers using doubles can represent exactly.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl RecordEntry {
+    /// Renders the entry as a JSON value. Blobs are encoded as base64 strings, and integers
+    /// that cannot be represented exactly as a double are emitted as strings.
+    pub fn to_json(&self) -> String {
+        match self {
+            RecordEntry::Null => "null".to_string(),
+            RecordEntry::Integer(x) if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(x) => x.to_string(),
+            RecordEntry::Integer(x) => format!("\"{}\"", x),
+            // JSON has no NaN or infinities: NaN becomes null like in SQLite, and infinities become
+            // numbers too large for a double, which parsers read back as infinities
+            RecordEntry::Float(x) if x.is_nan() => "null".to_string(),
+            RecordEntry::Float(x) if x.is_infinite() => if *x > 0.0 { "9e999" } else { "-9e999" }.to_string(),
+            RecordEntry::Float(x) => format!("{:?}", x),
+            RecordEntry::Text(x) => json_string(x),
+            RecordEntry::Blob(x) => format!("\"{}\"", base64(x)),
+        }
+    }
+}
+
+impl Record {
+    /// Renders the record as a JSON object keyed by the column names of the given table.
+    ///
+    /// The record is taken to be in declared order. A record as stored in the b-tree holds NULL
+    /// for the `INTEGER PRIMARY KEY` column, so rows should rather go through [`Row::to_json`].
+    pub fn to_json(&self, table: &TableDef) -> String {
+        let mut result = String::from("{");
+
+        for (index, (name, entry)) in self.with_columns(table).iter().enumerate() {
+            if index > 0 {
+                result.push(',');
+            }
+            result.push_str(&json_string(name));
+            result.push(':');
+            result.push_str(&entry.to_json());
+        }
+
+        result.push('}');
+        result
+    }
+}
+
+impl Row {
+    /// Renders the row as a JSON object keyed by the column names of the table it came from,
+    /// with the `INTEGER PRIMARY KEY` column, if any, holding the rowid.
+    pub fn to_json(&self, table: &TableDef) -> String {
+        self.record.to_json(table)
+    }
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            '\u{8}' => result.push_str("\\b"),
+            '\u{c}' => result.push_str("\\f"),
+            c if (c as u32) < 0x20 => write!(result, "\\u{:04x}", c as u32).unwrap(),
+            c => result.push(c),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+pub(crate) fn base64(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                result.push(BASE64_ALPHABET[(group >> (18 - 6 * index) & 0x3F) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Record, RecordEntry, TableDef};
+
+    #[test]
+    fn renders_a_row_as_a_json_object() {
+        let table = TableDef::parse("CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT, score REAL, data BLOB, big INTEGER, note)").unwrap();
+        let record = Record {
+            entries: vec![
+                RecordEntry::Integer(1),
+                RecordEntry::Text("Alice \"A\"\n".to_string()),
+                RecordEntry::Float(2.5),
+                RecordEntry::Blob(vec![0, 1, 2, 255]),
+                RecordEntry::Integer(9007199254740993),
+                RecordEntry::Null,
+            ],
+            serial_types: Vec::new(),
+        };
+
+        assert_eq!(record.to_json(&table), r#"{"id":1,"name":"Alice \"A\"\n","score":2.5,"data":"AAEC/w==","big":"9007199254740993","note":null}"#);
+    }
+
+    #[test]
+    fn integers_within_the_range_of_doubles_are_numbers() {
+        assert_eq!(RecordEntry::Integer(9007199254740991).to_json(), "9007199254740991");
+        assert_eq!(RecordEntry::Integer(-9007199254740991).to_json(), "-9007199254740991");
+        assert_eq!(RecordEntry::Integer(-9007199254740992).to_json(), "\"-9007199254740992\"");
+    }
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(super::base64(b""), "");
+        assert_eq!(super::base64(b"f"), "Zg==");
+        assert_eq!(super::base64(b"fo"), "Zm8=");
+        assert_eq!(super::base64(b"foo"), "Zm9v");
+        assert_eq!(super::base64(b"foobar"), "Zm9vYmFy");
+    }
+}
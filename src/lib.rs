@@ -0,0 +1,60 @@
+pub use blob_reader::BlobReader;
+pub use database::Database;
+pub use database_builder::DatabaseBuilder;
+pub use compare::{compare_entries, compare_entries_collated, compare_records, compare_text, Collation};
+pub use csv::{write_csv, write_csv_header, write_csv_row};
+pub use filter::{ColumnPredicate, Comparison, Filter};
+pub use freelist::Freelist;
+pub use from_row::{FromEntry, FromRow};
+pub use header::{DatabaseSizeSource, FileHeader, VacuumMode};
+pub use index_iterator::IndexIterator;
+pub use integrity::{check_integrity, IntegrityProblem};
+pub use journal::Journal;
+pub use page::{local_payload_size, FilePage, FilePageCell, FilePageCellPointers, FilePageHeader, FilePageType};
+pub use page_info::{PageInfo, PageKind};
+pub use page_iterator::PageIterator;
+pub use pager::{CellIterator, Pager, PagerStats, TruncatedFile};
+pub use ptrmap::{PtrmapEntry, PtrmapPageType};
+pub use record::{ReadOptions, Record, RecordEntry, RecordView, UnknownSerialTypes};
+pub use recovery::recover_deleted;
+pub use schema::{IndexInfo, Schema, SchemaEntry, SchemaEntryType, TableInfo, SCHEMA_ROOT_PAGE};
+pub use table::{Row, Table, TableRows};
+pub use table_def::{Affinity, ColumnDef, Generated, TableDef};
+pub use table_iterator::TableIterator;
+pub use trace::{TraceDecision, TraceStep};
+pub use wal::Wal;
+pub use walk::{walk_index, walk_table, WalkSummary};
+
+mod blob_reader;
+mod compare;
+mod csv;
+mod database;
+mod database_builder;
+mod filter;
+mod freelist;
+mod from_row;
+mod header;
+mod index_iterator;
+mod integrity;
+mod journal;
+mod json;
+mod page;
+mod page_info;
+mod page_iterator;
+mod pager;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod ptrmap;
+mod record;
+mod recovery;
+mod schema;
+#[cfg(feature = "serde")]
+mod serialize;
+mod sql;
+mod table;
+mod table_def;
+mod table_iterator;
+mod trace;
+mod varint;
+mod wal;
+mod walk;
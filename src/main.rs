@@ -1,344 +1,653 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind, Read, Seek, Write};
+use std::ops::ControlFlow;
 
-use byteorder::{BigEndian, ReadBytesExt};
+use sqlite_reader::{walk_index, walk_table, write_csv_header, write_csv_row, Affinity, Comparison, Database, DatabaseSizeSource, FilePage, FilePageHeader, FilePageType, Filter, PageInfo, PageKind, Pager, ReadOptions, Record, RecordEntry, Row, Schema, TableDef, WalkSummary, SCHEMA_ROOT_PAGE};
 
-use crate::FilePageType::{IndexInterior, IndexLeaf, TableInterior, TableLeaf};
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Debug,
+    Json,
+    Csv,
+}
 
-trait ReadVarExt: byteorder::ReadBytesExt {
-    fn read_var64(&mut self) -> std::io::Result<i64> {
-        let mut res = 0u64;
+struct Args {
+    path: String,
+    format: Format,
+    table: Option<String>,
+    all_tables: bool,
+    include_internal: bool,
+    where_clause: Option<String>,
+    rowid: Option<i64>,
+    page: Option<u32>,
+    hexdump_page: Option<u32>,
+    integrity_check: bool,
+    stats: bool,
+    limit: Option<usize>,
+    cache_size: Option<usize>,
+    max_field_width: Option<usize>,
+}
 
-        loop {
-            let val = self.read_u8()? as u64;
+impl Args {
+    /// Parses the command line arguments, not including the name of the program.
+    fn parse<I>(args: I) -> std::io::Result<Self>
+        where I: IntoIterator<Item = String>
+    {
+        let mut path = None;
+        let mut format = Format::Debug;
+        let mut table = None;
+        let mut all_tables = false;
+        let mut include_internal = false;
+        let mut where_clause = None;
+        let mut rowid = None;
+        let mut page = None;
+        let mut hexdump_page = None;
+        let mut integrity_check = false;
+        let mut stats = false;
+        let mut limit = None;
+        let mut cache_size = None;
+        let mut max_field_width = None;
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--format" => format = match args.next().as_deref() {
+                    Some("debug") => Format::Debug,
+                    Some("json") => Format::Json,
+                    Some("csv") => Format::Csv,
+                    x => return Err(Error::new(ErrorKind::InvalidInput, format!("Unknown format: {:?}, expected debug, json or csv", x))),
+                },
+                "--table" => table = Some(args.next().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected a table name after --table"))?),
+                "--all-tables" => all_tables = true,
+                "--include-internal" => include_internal = true,
+                "--where" => where_clause = Some(args.next().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected an expression like \"column=value\" after --where"))?),
+                "--rowid" => rowid = Some(args.next()
+                    .and_then(|rowid| rowid.parse().ok())
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected a rowid after --rowid"))?),
+                "--page" => page = Some(args.next()
+                    .and_then(|page| page.parse().ok())
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected a page number after --page"))?),
+                "--hexdump-page" => hexdump_page = Some(args.next()
+                    .and_then(|page| page.parse().ok())
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected a page number after --hexdump-page"))?),
+                "--integrity-check" => integrity_check = true,
+                "--stats" => stats = true,
+                "--limit" => limit = Some(args.next()
+                    .and_then(|limit| limit.parse().ok())
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected a number of rows after --limit"))?),
+                "--cache-size" => cache_size = Some(args.next()
+                    .and_then(|size| size.parse().ok())
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected a number of pages after --cache-size"))?),
+                "--max-field-width" => max_field_width = Some(args.next()
+                    .and_then(|width| width.parse().ok())
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Expected a number of characters after --max-field-width"))?),
+                x if x.starts_with("--") => return Err(Error::new(ErrorKind::InvalidInput, format!("Unknown option: {}", x))),
+                _ if path.is_none() => path = Some(arg),
+                x => return Err(Error::new(ErrorKind::InvalidInput, format!("Unexpected argument: {}", x))),
+            }
+        }
 
-            res = (res << 7) | (val & 0x7F);
+        Ok(Args {
+            path: path.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "No input parameter specified"))?,
+            format,
+            table,
+            all_tables,
+            include_internal,
+            where_clause,
+            rowid,
+            page,
+            hexdump_page,
+            integrity_check,
+            stats,
+            limit,
+            cache_size,
+            max_field_width,
+        })
+    }
+}
 
-            if val & 0x80 == 0 {
-                return Ok(res as i64);
+/// How the rows of a table are rendered.
+struct Output {
+    format: Format,
+    table: Option<TableDef>,
+    /// Longest text or blob printed in full in the debug format. JSON and CSV are never truncated.
+    max_field_width: Option<usize>,
+}
+
+impl Output {
+    fn print_header(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        match (self.format, &self.table) {
+            (Format::Csv, Some(table)) => write_csv_header(out, table),
+            _ => Ok(()),
+        }
+    }
+
+    fn print_row(&self, out: &mut dyn Write, rowid: i64, record: &Record) -> std::io::Result<()> {
+        // The INTEGER PRIMARY KEY column is stored as NULL, and printed as the rowid it stands for.
+        // VIRTUAL generated columns are not stored at all, and printed as NULL in their place,
+        // while columns added after the row was written are printed as their default
+        let record = match &self.table {
+            Some(table) if table.rowid_alias().is_some() || table.has_virtual_columns() || record.len() < table.columns.len() => {
+                Cow::Owned(Row::from_stored(table, Some(rowid), record.clone()).record)
             }
+            _ => Cow::Borrowed(record),
+        };
+
+        match (self.format, &self.table) {
+            (Format::Json, Some(table)) => writeln!(out, "{}", record.to_json(table)),
+            (Format::Csv, _) => write_csv_row(out, &record),
+            _ => match self.max_field_width {
+                Some(max_width) => writeln!(out, "[{}]: {:.*}", rowid, max_width, record),
+                None => writeln!(out, "[{}]: {}", rowid, record),
+            },
+        }
+    }
+
+    fn print_without_rowid_row(&self, out: &mut dyn Write, record: &Record) -> std::io::Result<()> {
+        match (self.format, &self.table) {
+            (Format::Json, Some(table)) => writeln!(out, "{}", record.to_json(table)),
+            (Format::Csv, _) => write_csv_row(out, record),
+            _ => match self.max_field_width {
+                Some(max_width) => writeln!(out, "{:.*}", max_width, record),
+                None => writeln!(out, "{}", record),
+            },
         }
     }
 }
 
-impl<R: Read> ReadVarExt for R {}
+/// Operators of a `--where` expression. Two-character operators come first, so that `<=`
+/// is not taken for `<` followed by a value starting with `=`.
+const OPERATORS: [(&str, Comparison); 7] = [
+    ("<=", Comparison::LessOrEqual),
+    (">=", Comparison::GreaterOrEqual),
+    ("!=", Comparison::NotEqual),
+    ("<>", Comparison::NotEqual),
+    ("=", Comparison::Equal),
+    ("<", Comparison::Less),
+    (">", Comparison::Greater),
+];
+
+/// Parses a single `column op value` expression given with `--where` into a filter on the table.
+///
+/// A comparison of the rowid, either by its name or by its `INTEGER PRIMARY KEY` alias, is
+/// returned separately: the filter only narrows down the range of rowids, and cannot exclude one.
+fn parse_where(expression: &str, table: &TableDef) -> std::io::Result<(Filter, Option<(Comparison, i64)>)> {
+    let (start, operator, comparison) = OPERATORS.iter()
+        .filter_map(|&(operator, comparison)| expression.find(operator).map(|start| (start, operator, comparison)))
+        .min_by_key(|&(start, _, _)| start)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Expected an expression like \"column=value\", got {:?}", expression)))?;
+
+    let name = expression[..start].trim();
+    let value = expression[start + operator.len()..].trim();
+
+    let index = table.column_index(name);
+    let is_rowid = match index {
+        Some(index) => table.rowid_alias() == Some(index),
+        None => !table.without_rowid && ["rowid", "oid", "_rowid_"].iter().any(|x| x.eq_ignore_ascii_case(name)),
+    };
+
+    if is_rowid {
+        let rowid = match Affinity::Integer.apply(&parse_value(value)?).into_owned() {
+            RecordEntry::Integer(rowid) => rowid,
+            x => return Err(Error::new(ErrorKind::InvalidInput, format!("Expected an integer to compare the rowid with, got {}", x))),
+        };
+
+        // The bounds only skip subtrees that cannot match, the comparison itself is checked for each row
+        let (min_rowid, max_rowid) = match comparison {
+            Comparison::Equal => (Some(rowid), Some(rowid)),
+            Comparison::NotEqual => (None, None),
+            Comparison::Less => (None, Some(rowid.saturating_sub(1))),
+            Comparison::LessOrEqual => (None, Some(rowid)),
+            Comparison::Greater => (Some(rowid.saturating_add(1)), None),
+            Comparison::GreaterOrEqual => (Some(rowid), None),
+        };
+
+        let filter = Filter {
+            min_rowid,
+            max_rowid,
+            columns: Vec::new(),
+        };
+
+        return Ok((filter, Some((comparison, rowid))));
+    }
+
+    let index = index.ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("No such column in table {}: {}", table.name, name)))?;
+    if table.storage_index(index).is_none() {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Column {} is a VIRTUAL generated column, which is not stored and cannot be compared", name)));
+    }
+    let value = parse_value(value)?;
 
-#[derive(Debug)]
-struct FileHeader {
-    page_size: u16,
-    database_size: u32,
-    text_encoding: u32,
+    Ok((Filter::default().and_table_column(table, index, comparison, value), None))
 }
 
-impl FileHeader {
-    fn read<R>(reader: &mut R) -> std::io::Result<Self>
-        where R: Read + Seek
-    {
-        reader.seek(SeekFrom::Start(16))?;
-        let page_size = reader.read_u16::<BigEndian>()?;
+/// Parses a literal of a `--where` expression. The affinity of the column it is compared with
+/// is applied by the filter, see [`Filter::and_table_column`].
+fn parse_value(value: &str) -> std::io::Result<RecordEntry> {
+    if let Some(text) = value.strip_prefix('\'').and_then(|value| value.strip_suffix('\'')) {
+        return Ok(RecordEntry::Text(text.replace("''", "'")));
+    }
 
-        reader.seek(SeekFrom::Start(28))?;
-        let database_size = reader.read_u32::<BigEndian>()?;
+    if let Some(hex) = value.strip_prefix("x'").or_else(|| value.strip_prefix("X'")).and_then(|value| value.strip_suffix('\'')) {
+        return (0..hex.len()).step_by(2)
+            .map(|start| hex.get(start..start + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+            .collect::<Option<Vec<_>>>()
+            .map(RecordEntry::Blob)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Invalid blob literal: {}", value)));
+    }
 
-        reader.seek(SeekFrom::Start(56))?;
-        let text_encoding = reader.read_u32::<BigEndian>()?;
+    if value.eq_ignore_ascii_case("NULL") {
+        return Ok(RecordEntry::Null);
+    }
 
-        reader.seek(SeekFrom::Start(100))?;
+    // Rust also parses words like "inf" and "NaN", which are not numbers in SQL
+    let number = value.chars().all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'))
+        .then(|| value.parse().map(RecordEntry::Integer).ok().or_else(|| value.parse().map(RecordEntry::Float).ok()))
+        .flatten();
 
-        Ok(FileHeader {
-            page_size,
-            database_size,
-            text_encoding,
-        })
+    match number {
+        Some(number) => Ok(number),
+        None => Err(Error::new(ErrorKind::InvalidInput, format!("Expected a number, a 'quoted' string, a blob literal or NULL, got {:?}", value))),
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq)]
-enum FilePageType {
-    TableInterior,
-    TableLeaf,
-    IndexInterior,
-    IndexLeaf,
+/// Counts a printed row against the limit, stopping the walk once it is reached.
+fn count_down(remaining: &mut usize) -> ControlFlow<()> {
+    *remaining -= 1;
+    if *remaining == 0 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
 }
 
-impl FilePageType {
-    fn read<R>(reader: &mut R) -> std::io::Result<Self>
-        where R: Read
-    {
-        match reader.read_u8()? {
-            0x2 => Ok(IndexInterior),
-            0x5 => Ok(TableInterior),
-            0xA => Ok(IndexLeaf),
-            0xD => Ok(TableLeaf),
-            x => Err(Error::new(ErrorKind::InvalidData, format!("Unknown file page type: {}", x))),
-        }
+/// Rows of the page counts printed by `--stats`, indexed by [`stats_row`].
+const STATS_LABELS: [&str; 11] = ["Table interior", "Table leaf", "Index interior", "Index leaf", "Overflow", "Freelist trunk", "Freelist leaf", "Pointer map", "Lock byte", "Unused", "Unknown"];
+
+/// Row of `--stats` that counts a page. A b-tree page without a page type is counted as
+/// unknown rather than as any particular kind.
+fn stats_row(info: &PageInfo) -> usize {
+    match (info.kind, info.page_type) {
+        (PageKind::BTree, Some(FilePageType::TableInterior)) => 0,
+        (PageKind::BTree, Some(FilePageType::TableLeaf)) => 1,
+        (PageKind::BTree, Some(FilePageType::IndexInterior)) => 2,
+        (PageKind::BTree, Some(FilePageType::IndexLeaf)) => 3,
+        (PageKind::Overflow, _) => 4,
+        (PageKind::FreelistTrunk, _) => 5,
+        (PageKind::FreelistLeaf, _) => 6,
+        (PageKind::Ptrmap, _) => 7,
+        (PageKind::LockByte, _) => 8,
+        (PageKind::Unused, _) => 9,
+        (PageKind::BTree, None) => 10,
     }
 }
 
-#[derive(Debug)]
-struct FilePageHeader {
-    typ: FilePageType,
-    first_free_block: u16,
-    cells_count: u16,
-    cells_content_start: u16,
-    cells_content_fragmented_bytes: u8,
-    right_most_pointer: Option<u32>,
-}
+/// Prints the number of pages of each kind, and how full the b-tree pages are. Cells are
+/// parsed without decoding their payloads, as only their sizes matter.
+fn print_stats<R>(out: &mut dyn Write, database: &mut Database<R>) -> std::io::Result<()>
+    where R: Read + Seek
+{
+    let options = *database.pager().read_options();
+    database.set_read_options(ReadOptions {
+        decode_payload: false,
+        ..options
+    });
+
+    let usable_size = database.header().usable_size() as usize;
+    let pages = PageInfo::read_all(database.pager())?;
+    let mut counts = [0; STATS_LABELS.len()];
+    let mut btree_pages = 0;
+    let mut free_bytes = 0;
+    let mut unreadable_pages = 0;
+
+    for info in pages.iter() {
+        counts[stats_row(info)] += 1;
+
+        if info.kind == PageKind::BTree {
+            match database.page(info.page_number) {
+                Ok(page) => {
+                    btree_pages += 1;
+                    free_bytes += page.free_bytes();
+                }
+                Err(_) => unreadable_pages += 1,
+            }
+        }
+    }
 
-impl FilePageHeader {
-    fn read<R>(reader: &mut R) -> std::io::Result<Self>
-        where R: Read
-    {
-        let typ = FilePageType::read(reader)?;
-        let first_free_block = reader.read_u16::<BigEndian>()?;
-        let cells_count = reader.read_u16::<BigEndian>()?;
-        let cells_content_start = reader.read_u16::<BigEndian>()?;
-        let cells_content_fragmented_bytes = reader.read_u8()?;
-
-        let right_most_pointer = match typ {
-            TableInterior | IndexInterior => Some(reader.read_u32::<BigEndian>()?),
-            _ => None
-        };
+    writeln!(out, "{:<16} {:>10}", "Page type", "Pages")?;
+    for (label, count) in STATS_LABELS.iter().zip(counts.iter()) {
+        writeln!(out, "{:<16} {:>10}", label, count)?;
+    }
+    writeln!(out, "{:<16} {:>10}", "Total", pages.len())?;
+    writeln!(out)?;
 
-        Ok(FilePageHeader {
-            typ,
-            first_free_block,
-            cells_count,
-            cells_content_start,
-            cells_content_fragmented_bytes,
-            right_most_pointer,
-        })
+    writeln!(out, "{:<16} {:>10}", "Free bytes", free_bytes)?;
+    if btree_pages > 0 {
+        let fill_factor = 100.0 - free_bytes as f64 * 100.0 / (btree_pages * usable_size) as f64;
+        writeln!(out, "{:<16} {:>9.1}%", "Average fill", fill_factor)?;
     }
+    if unreadable_pages > 0 {
+        writeln!(out, "{:<16} {:>10}", "Unreadable", unreadable_pages)?;
+    }
+
+    Ok(())
 }
 
-#[derive(Debug)]
-struct FilePage {
-    header: FilePageHeader,
-    cells: Vec<FilePageCell>,
+/// Prints the header and the cells of a single page, without following any pointers.
+/// The header is printed even if the cells cannot be read, to help finding out why.
+fn print_page_structure<R>(out: &mut dyn Write, pager: &mut Pager<R>, page_number: u32) -> std::io::Result<()>
+    where R: Read + Seek
+{
+    let data = pager.read_raw(page_number)?;
+    let header = FilePageHeader::read(&mut &data[FilePage::header_start(page_number)..])?;
+
+    writeln!(out, "Page {}: {:#?}", page_number, header)?;
+
+    for (index, cell) in pager.cells(page_number)?.enumerate() {
+        writeln!(out, "Cell {}: {:?}", index, cell?)?;
+    }
+
+    Ok(())
 }
 
-impl FilePage {
-    fn read<R>(reader: &mut R, file_header: &FileHeader) -> std::io::Result<Self>
-        where R: Read + Seek
-    {
-        let start = reader.stream_position()? & !(file_header.page_size as u64 - 1);
-        let header = FilePageHeader::read(reader)?;
+/// Prints the raw bytes of a page, split into labelled ranges for the header fields, the cell
+/// pointer array, the cells, the freeblocks and the unallocated space. Bytes not covered by
+/// any of them, such as fragments, and cells that cannot be read are labelled as such.
+fn print_page_hexdump<R>(out: &mut dyn Write, pager: &mut Pager<R>, page_number: u32) -> std::io::Result<()>
+    where R: Read + Seek
+{
+    let data = pager.read_raw(page_number)?;
+    let usable_size = pager.header().usable_size() as usize;
+    let header_start = FilePage::header_start(page_number);
+    let mut ranges = Vec::new();
+
+    if header_start > 0 {
+        ranges.push((0, header_start, "File header".to_string()));
+    }
 
-        let mut cell_offsets = Vec::new();
-        let mut cells = Vec::new();
+    match FilePageHeader::read(&mut &data[header_start..]) {
+        Ok(header) => {
+            let fields = [("Page type", 1), ("First freeblock", 2), ("Cell count", 2), ("Cell content start", 2), ("Fragmented bytes", 1), ("Right-most pointer", 4)];
+            let mut offset = header_start;
+            let field_count = if header.right_most_pointer.is_some() { fields.len() } else { fields.len() - 1 };
+            for (name, size) in fields.iter().take(field_count) {
+                ranges.push((offset, offset + size, name.to_string()));
+                offset += size;
+            }
 
-        for _ in 0..header.cells_count {
-            cell_offsets.push(reader.read_u16::<BigEndian>()?);
-        }
+            let pointers_end = offset + header.cells_count as usize * 2;
+            ranges.push((offset, pointers_end.min(data.len()), "Cell pointers".to_string()));
+            if pointers_end < header.content_start() {
+                ranges.push((pointers_end, header.content_start().min(usable_size), "Unallocated".to_string()));
+            }
 
-        for cell in cell_offsets.iter() {
-            reader.seek(SeekFrom::Start(start + *cell as u64))?;
-            cells.push(FilePageCell::read(reader, &header, file_header)?);
+            match pager.get(page_number) {
+                Ok(page) => {
+                    let page_start = pager.header().page_offset(page_number);
+                    for (index, cell) in page.cells.iter().enumerate() {
+                        let start = (cell.file_offset - page_start) as usize;
+                        ranges.push((start, start + cell.size as usize, format!("Cell {}", index)));
+                    }
+                    for (offset, size) in page.freeblocks.iter() {
+                        ranges.push((*offset as usize, *offset as usize + *size as usize, "Freeblock".to_string()));
+                    }
+                }
+                Err(err) => writeln!(out, "Cells could not be read: {}", err)?,
+            }
         }
+        Err(err) => writeln!(out, "Page header could not be read: {}", err)?,
+    }
 
-        Ok(FilePage {
-            header,
-            cells,
-        })
+    if usable_size < data.len() {
+        ranges.push((usable_size, data.len(), "Reserved".to_string()));
     }
-}
 
+    ranges.sort();
 
-#[derive(Debug)]
-struct FilePageCell {
-    payload: Option<Record>,
-    left_child_page_number: Option<u32>,
-    first_overflow_page_number: Option<u32>,
-    rowid: Option<i64>,
-}
+    let mut position = 0;
+    for (start, end, label) in ranges {
+        if position < start {
+            print_hexdump(out, &data, position, start, "Unaccounted")?;
+        }
+        print_hexdump(out, &data, start, end, &label)?;
+        position = position.max(end);
+    }
 
-impl FilePageCell {
-    fn read<R>(reader: &mut R, page_header: &FilePageHeader, file_header: &FileHeader) -> std::io::Result<Self>
-        where R: Read + Seek
-    {
-        let left_child_page_number = match page_header.typ {
-            TableInterior | IndexInterior => Some(reader.read_u32::<BigEndian>()?),
-            _ => None
-        };
+    if position < data.len() {
+        print_hexdump(out, &data, position, data.len(), "Unaccounted")?;
+    }
 
-        let payload_length = match page_header.typ {
-            TableLeaf | IndexLeaf | IndexInterior => Some(reader.read_var64()?),
-            _ => None
-        };
+    Ok(())
+}
 
-        let rowid = match page_header.typ {
-            TableLeaf | TableInterior => Some(reader.read_var64()?),
-            _ => None
-        };
+/// Prints a labelled range of bytes, 16 per line, with their offsets within the page.
+/// Like in `hexdump`, a run of lines repeating the previous one is printed as a single `*`.
+fn print_hexdump(out: &mut dyn Write, data: &[u8], start: usize, end: usize, label: &str) -> std::io::Result<()> {
+    writeln!(out, "{} ({}..{}, {} bytes)", label, start, end, end - start)?;
 
-        let payload = if payload_length.is_some() {
-            Some(Record::read(reader, file_header)?)
-        } else {
-            None
-        };
+    let mut previous: Option<&[u8]> = None;
+    let mut repeating = false;
 
-        let first_overflow_page_number = match page_header.typ {
-            // TableLeaf | IndexLeaf | IndexInterior => Some(reader.read_u32::<BigEndian>()?),
-            _ => None
-        };
+    for line_start in (start..end).step_by(16) {
+        let line = &data[line_start..(line_start + 16).min(end)];
+        if previous == Some(line) {
+            if !repeating {
+                writeln!(out, "  *")?;
+                repeating = true;
+            }
+            continue;
+        }
+        previous = Some(line);
+        repeating = false;
 
-        Ok(FilePageCell {
-            payload,
-            left_child_page_number,
-            first_overflow_page_number,
-            rowid,
-        })
+        let hex = line.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(" ");
+        let text = line.iter().map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }).collect::<String>();
+        writeln!(out, "  {:06x}  {:<47}  {}", line_start, hex, text)?;
     }
-}
 
-#[derive(Debug)]
-enum RecordEntry {
-    Null,
-    Integer(i64),
-    Float(f64),
-    Blob(Vec<u8>),
-    Text(String),
+    Ok(())
 }
 
-#[derive(Debug)]
-struct Record {
-    entries: Vec<RecordEntry>,
+fn main() -> std::io::Result<()> {
+    let args = Args::parse(std::env::args().skip(1))?;
+    run(&mut std::io::stdout().lock(), &args)
 }
 
-impl Record {
-    fn read<R>(reader: &mut R, file_header: &FileHeader) -> std::io::Result<Self>
-        where R: Read + Seek
-    {
-        let record_start = reader.stream_position()?;
-        let record_size = reader.read_var64()?;
-        let record_end = record_start + record_size as u64;
+/// Runs the command given on the command line, writing its output to `out` and warnings and
+/// errors found along the way to standard error.
+fn run(out: &mut dyn Write, args: &Args) -> std::io::Result<()> {
+    let mut builder = Database::builder().unknown_encoding_as_utf8(true);
+    if let Some(cache_size) = args.cache_size {
+        builder = builder.cache_capacity(cache_size);
+    }
 
-        let mut entry_types = Vec::new();
-        let mut entries = Vec::new();
+    let mut database = builder.open(&args.path)?;
+
+    if database.header().database_size_source() == DatabaseSizeSource::FileLength {
+        eprintln!("Warning: database size in the header is stale, using the file length instead ({} pages)", database.header().database_size());
+    }
+
+    if !(1..=3).contains(&database.header().text_encoding()) {
+        eprintln!("Warning: database has an unknown text encoding {}, decoding text as UTF-8", database.header().text_encoding());
+    }
+
+    if let Some(journal) = database.hot_journal() {
+        eprintln!("Warning: database has a hot rollback journal from an unfinished transaction, {} pages may be inconsistent", journal.dirty_pages().len());
+    }
 
-        while reader.stream_position()? < record_end {
-            entry_types.push(reader.read_var64()?);
+    // Like PRAGMA integrity_check, a database without problems is reported as ok
+    if args.integrity_check {
+        let problems = database.integrity_check();
+        if problems.is_empty() {
+            writeln!(out, "ok")?;
+            return Ok(());
         }
 
-        for typ in entry_types.iter() {
-            entries.push(match *typ {
-                0 => RecordEntry::Null,
-                1 => RecordEntry::Integer(reader.read_i8()? as i64),
-                2 => RecordEntry::Integer(reader.read_i16::<BigEndian>()? as i64),
-                3 => RecordEntry::Integer(reader.read_i24::<BigEndian>()? as i64),
-                4 => RecordEntry::Integer(reader.read_i32::<BigEndian>()? as i64),
-                5 => RecordEntry::Integer(reader.read_i48::<BigEndian>()? as i64),
-                6 => RecordEntry::Integer(reader.read_i64::<BigEndian>()?),
-                7 => RecordEntry::Float(reader.read_f64::<BigEndian>()?),
-                8 => RecordEntry::Integer(0),
-                9 => RecordEntry::Integer(1),
-                x if x >= 12 && x % 2 == 0 => {
-                    let mut buf = vec![0; ((x - 12) / 2) as usize];
-                    reader.read_exact(&mut buf)?;
-                    RecordEntry::Blob(buf)
-                }
-                x if x >= 13 && x % 2 == 1 => {
-                    assert_eq!(file_header.text_encoding, 1);
-                    let mut buf = vec![0; ((x - 13) / 2) as usize];
-                    reader.read_exact(&mut buf)?;
-                    RecordEntry::Text(String::from_utf8(buf).unwrap())
-                }
-                x => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown record type: {}", x)))
-            })
+        for problem in problems.iter() {
+            writeln!(out, "{}", problem)?;
         }
 
-        Ok(Record {
-            entries
-        })
+        return Err(Error::new(ErrorKind::InvalidData, format!("{} problems found", problems.len())));
     }
-}
 
-struct Filter {
-    min_rowid: Option<i64>,
-    max_rowid: Option<i64>,
-}
+    if args.stats {
+        return print_stats(out, &mut database);
+    }
 
-impl Filter {
-    fn matches(&self, cell: &FilePageCell) -> bool {
-        let mut result = true;
+    if let Some(page_number) = args.hexdump_page {
+        return print_page_hexdump(out, database.pager(), page_number);
+    }
 
-        result &= match self.min_rowid {
-            Some(min_rowid) => cell.rowid.map(|rowid| rowid >= min_rowid).unwrap_or(false),
-            None => true,
-        };
+    if let Some(page_number) = args.page {
+        return print_page_structure(out, database.pager(), page_number);
+    }
 
-        result &= match self.max_rowid {
-            Some(max_rowid) => cell.rowid.map(|rowid| rowid <= max_rowid).unwrap_or(false),
-            None => true,
-        };
+    if args.all_tables && (args.table.is_some() || args.where_clause.is_some() || args.rowid.is_some()) {
+        return Err(Error::new(ErrorKind::InvalidInput, "--all-tables cannot be combined with --table, --where or --rowid"));
+    }
 
-        result
+    if args.rowid.is_some() && args.where_clause.is_some() {
+        return Err(Error::new(ErrorKind::InvalidInput, "--rowid cannot be combined with --where"));
     }
-}
 
-fn print_page_contents(pages: &HashMap<u32, FilePage>, page: &FilePage, filter: &Filter) {
-    match &page.header.typ {
-        TableInterior => {
-            for cell in page.cells.iter().filter(|cell| filter.matches(cell)) {
-                let left_child_page_number = cell.left_child_page_number.unwrap();
-                let left_child_page = pages.get(&left_child_page_number).unwrap();
-                print_page_contents(pages, left_child_page, filter);
-            }
-            print_page_contents(pages, pages.get(&page.header.right_most_pointer.unwrap()).unwrap(), filter);
+    // Without a table name, the schema itself is printed
+    let tables = match &args.table {
+        _ if args.all_tables => {
+            let schema = Schema::read(&mut database)?;
+            schema.tables(args.include_internal)
+                .map(|entry| Ok((entry.root_page.unwrap_or_default(), entry.table_def()?)))
+                .collect::<std::io::Result<Vec<_>>>()?
         }
-        TableLeaf => {
-            for cell in page.cells.iter().filter(|cell| filter.matches(cell)) {
-                let rowid = cell.rowid.unwrap();
-                let record = cell.payload.as_ref().unwrap();
-                println!("[{:?}]: {:?}", rowid, record.entries);
-            }
+        Some(name) => {
+            let table = database.table(name)?
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("No such table: {}", name)))?;
+            vec![(table.root_page, table.def)]
         }
-        IndexInterior => {
-            for cell in page.cells.iter().filter(|cell| filter.matches(cell)) {
-                let left_child_page_number = cell.left_child_page_number.unwrap();
-                let left_child_page = pages.get(&left_child_page_number).unwrap();
-                print_page_contents(pages, left_child_page, filter);
-
-                let record = cell.payload.as_ref().unwrap();
-                println!("{:?} => {:?}", record.entries[0], record.entries[1]);
-            }
-        }
-        IndexLeaf => {
-            for cell in page.cells.iter().filter(|cell| filter.matches(cell)) {
-                let record = cell.payload.as_ref().unwrap();
-                println!("{:?} => {:?}", record.entries[0], record.entries[1]);
-            }
+        None => vec![(SCHEMA_ROOT_PAGE, Schema::master_table_def())],
+    };
+
+    let mut summary = WalkSummary::default();
+    for (root_page, table) in tables {
+        if args.all_tables && args.format == Format::Debug {
+            writeln!(out, "-- {}", table.name)?;
         }
+
+        let table_summary = dump_table(out, &mut database, root_page, table, args)?;
+        summary.pages_visited += table_summary.pages_visited;
+        summary.errors.extend(table_summary.errors);
+    }
+
+    if summary.errors.is_empty() {
+        return Ok(());
+    }
+
+    for err in summary.errors.iter() {
+        eprintln!("Error: {}", err);
     }
+
+    Err(Error::new(ErrorKind::InvalidData, format!("{} of {} pages failed to parse", summary.errors.len(), summary.pages_visited)))
 }
 
-fn main() -> std::io::Result<()> {
-    let mut file = std::env::args().nth(1)
-        .map(File::open)
-        .unwrap_or_else(|| Err(Error::new(ErrorKind::InvalidInput, "No input parameter specified")))?;
-
-    let file_header = FileHeader::read(&mut file)?;
-    let mut file_pages = HashMap::new();
-
-    for page_index in 1..=file_header.database_size {
-        match FilePage::read(&mut file, &file_header) {
-            Ok(page) => {
-                // println!("{}: {:#?}", page_index, page);
-                file_pages.insert(page_index, page);
-            }
-            Err(err) => println!("{}", err)
-        };
+/// Prints the rows of a table in the format given on the command line.
+fn dump_table<R>(out: &mut dyn Write, database: &mut Database<R>, root_page: u32, table: TableDef, args: &Args) -> std::io::Result<WalkSummary>
+    where R: Read + Seek
+{
+    if args.rowid.is_some() && table.without_rowid {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Table {} is a WITHOUT ROWID table and has no rowids", table.name)));
+    }
+
+    let (filter, rowid_comparison) = match &args.where_clause {
+        Some(expression) => parse_where(expression, &table)?,
+        None => (Filter::default(), None),
+    };
+
+    let output = Output {
+        format: args.format,
+        table: Some(table),
+        max_field_width: args.max_field_width,
+    };
+
+    output.print_header(out)?;
+
+    // A single row is looked up by descending the b-tree, reading one page per level
+    if let Some(rowid) = args.rowid {
+        match database.find_row(root_page, rowid)? {
+            Some(record) => output.print_row(out, rowid, &record)?,
+            None => eprintln!("No such rowid: {}", rowid),
+        }
+        return Ok(WalkSummary::default());
+    }
 
-        file.seek(SeekFrom::Start(file_header.page_size as u64 * page_index as u64))?;
+    // Pages past the last row to print are never read
+    let mut remaining = args.limit.unwrap_or(usize::MAX);
+    if remaining == 0 {
+        return Ok(WalkSummary::default());
     }
 
-    let filter = Filter {
-        min_rowid: None, // Some(300),
-        max_rowid: None, // Some(320),
+    let summary = match &output.table {
+        Some(table) if table.without_rowid => walk_index(database.pager(), root_page, &filter, |record| {
+            output.print_without_rowid_row(out, &table.declared_order(record.clone()))?;
+            Ok(count_down(&mut remaining))
+        })?,
+        _ => walk_table(database.pager(), root_page, &filter, |rowid, record| {
+            if let Some((comparison, value)) = rowid_comparison {
+                if !comparison.matches(rowid.cmp(&value)) {
+                    return Ok(ControlFlow::Continue(()));
+                }
+            }
+            output.print_row(out, rowid, record)?;
+            Ok(count_down(&mut remaining))
+        })?,
     };
 
-    print_page_contents(&file_pages, file_pages.get(&0).unwrap(), &filter);
+    Ok(summary)
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the command with the given arguments on a database of `tests/fixtures`, returning its output.
+    fn output(fixture: &str, args: &[&str]) -> String {
+        let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+        let args = Args::parse(std::iter::once(path).chain(args.iter().map(|arg| arg.to_string()))).unwrap();
+
+        let mut out = Vec::new();
+        run(&mut out, &args).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn renders_rows_in_every_format() {
+        assert_eq!(output("rowid_alias.db", &["--table", "t"]), "[-3]: c|-3|2\n[10]: a|10|\n[20]: b|20|1\n");
+        assert_eq!(output("rowid_alias.db", &["--table", "t", "--format", "csv"]), "name,id,n\r\nc,-3,2\r\na,10,\r\nb,20,1\r\n");
+        assert_eq!(output("rowid_alias.db", &["--table", "t", "--format", "json", "--where", "n>1"]), "{\"name\":\"c\",\"id\":-3,\"n\":2}\n");
+    }
+
+    #[test]
+    fn renders_a_single_row_cut_short() {
+        assert_eq!(output("overflow.db", &["--table", "t", "--rowid", "1", "--max-field-width", "8"]), "[1]: 1|00000000… (5000 chars)|\n");
+        assert_eq!(output("overflow.db", &["--table", "t", "--rowid", "4"]), "");
+    }
+
+    #[test]
+    fn counts_every_page_once_in_stats() {
+        let stats = output("csv.db", &["--stats"]);
+        assert!(stats.starts_with("Page type             Pages\nTable interior            0\nTable leaf                2\n"), "{}", stats);
+        assert!(stats.contains("\nUnknown                   0\nTotal                     3\n"), "{}", stats);
+
+        // A b-tree page without a page type is not counted as an overflow page
+        let info = PageInfo {
+            page_number: 2,
+            kind: PageKind::BTree,
+            page_type: None,
+            cells_count: None,
+        };
+        assert_eq!(STATS_LABELS[stats_row(&info)], "Unknown");
+    }
+
+    #[test]
+    fn renders_every_table() {
+        assert_eq!(output("csv.db", &["--all-tables", "--limit", "1"]), "-- q\n[1]: 1|plain|x'00ff'|\n-- w\ny|1|\n");
+    }
+
+    #[test]
+    fn integrity_check_of_a_sound_database() {
+        assert_eq!(output("range.db", &["--integrity-check"]), "ok\n");
+    }
 }
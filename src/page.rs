@@ -0,0 +1,608 @@
+use std::collections::HashSet;
+use std::io::{Cursor, Error, ErrorKind, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{FileHeader, ReadOptions, Record};
+use crate::page::FilePageType::{IndexInterior, IndexLeaf, TableInterior, TableLeaf};
+use crate::varint::ReadVarExt;
+
+/// Size of the file header that precedes the b-tree header of page 1.
+const FILE_HEADER_SIZE: usize = 100;
+
+/// Number of bytes of a payload stored in the cell itself, the rest going to overflow pages,
+/// as computed by SQLite from the usable size of the pages and the type of the cell's page.
+///
+/// Table leaves keep up to `usable_size - 35` bytes locally, and index pages up to
+/// `(usable_size - 12) * 64 / 255 - 23`, so that at least four cells fit on a page. A payload
+/// that does not fit keeps at least `(usable_size - 12) * 32 / 255 - 23` bytes locally, and
+/// more if that makes its last overflow page full.
+pub fn local_payload_size(payload_length: u64, usable_size: u32, page_type: FilePageType) -> u64 {
+    let usable_size = usable_size as u64;
+    let max_local = match page_type {
+        TableLeaf => usable_size - 35,
+        _ => (usable_size - 12) * 64 / 255 - 23
+    };
+    let min_local = (usable_size - 12) * 32 / 255 - 23;
+
+    if payload_length <= max_local {
+        return payload_length;
+    }
+
+    let local_length = min_local + (payload_length - min_local) % (usable_size - 4);
+    if local_length <= max_local { local_length } else { min_local }
+}
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub enum FilePageType {
+    TableInterior,
+    TableLeaf,
+    IndexInterior,
+    IndexLeaf,
+}
+
+impl FilePageType {
+    pub fn read<R>(reader: &mut R) -> std::io::Result<Self>
+        where R: Read
+    {
+        match reader.read_u8()? {
+            0x2 => Ok(IndexInterior),
+            0x5 => Ok(TableInterior),
+            0xA => Ok(IndexLeaf),
+            0xD => Ok(TableLeaf),
+            x => Err(Error::new(ErrorKind::InvalidData, format!("Unknown file page type: {}", x))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FilePageHeader {
+    pub typ: FilePageType,
+    pub first_free_block: u16,
+    pub cells_count: u16,
+    pub cells_content_start: u16,
+    pub cells_content_fragmented_bytes: u8,
+    pub right_most_pointer: Option<u32>,
+}
+
+impl FilePageHeader {
+    pub fn read<R>(reader: &mut R) -> std::io::Result<Self>
+        where R: Read
+    {
+        let typ = FilePageType::read(reader)?;
+        let first_free_block = reader.read_u16::<BigEndian>()?;
+        let cells_count = reader.read_u16::<BigEndian>()?;
+        let cells_content_start = reader.read_u16::<BigEndian>()?;
+        let cells_content_fragmented_bytes = reader.read_u8()?;
+
+        let right_most_pointer = match typ {
+            TableInterior | IndexInterior => Some(reader.read_u32::<BigEndian>()?),
+            _ => None
+        };
+
+        Ok(FilePageHeader {
+            typ,
+            first_free_block,
+            cells_count,
+            cells_content_start,
+            cells_content_fragmented_bytes,
+            right_most_pointer,
+        })
+    }
+
+    /// Size of the header in bytes, which is larger for interior pages.
+    pub fn size(&self) -> usize {
+        match self.typ {
+            TableInterior | IndexInterior => 12,
+            TableLeaf | IndexLeaf => 8,
+        }
+    }
+
+    /// Offset of the cell content area within the page.
+    pub fn content_start(&self) -> usize {
+        // A cell content area starting at offset 0 is how a 65536-byte page stores an empty area
+        match self.cells_content_start {
+            0 => 65536,
+            x => x as usize,
+        }
+    }
+
+    /// Checks that the cell pointer array, the cell content area and the first freeblock lie
+    /// within the first `usable_size` bytes of a page whose header starts at `header_start`.
+    pub fn validate(&self, page_number: u32, usable_size: u32, header_start: usize) -> std::io::Result<()> {
+        let usable_size = usable_size as usize;
+        let pointers_end = header_start + self.size() + self.cells_count as usize * 2;
+        let content_start = self.content_start();
+
+        if pointers_end > usable_size {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has {} cells, which do not fit in the page", page_number, self.cells_count)));
+        }
+
+        if content_start < pointers_end || content_start > usable_size {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has cell content area at {}, outside of {}..={}", page_number, content_start, pointers_end, usable_size)));
+        }
+
+        if self.first_free_block != 0 && ((self.first_free_block as usize) < content_start || self.first_free_block as usize + 4 > usable_size) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has first freeblock at {}, outside of the cell content area", page_number, self.first_free_block)));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FilePage {
+    pub page_number: u32,
+    pub header: FilePageHeader,
+    pub cells: Vec<FilePageCell>,
+    /// Offset and size of each block in the chain of freeblocks, in ascending order.
+    pub freeblocks: Vec<(u16, u16)>,
+    /// Number of bytes of the page available for b-tree content, which excludes the reserved space.
+    pub usable_size: u32,
+}
+
+impl FilePage {
+    /// Reads the b-tree page with the given 1-based number.
+    ///
+    /// The b-tree header of page 1 follows the file header, but like on any other page,
+    /// cell offsets are relative to the start of the page.
+    pub fn read<R>(reader: &mut R, file_header: &FileHeader, page_number: u32, options: &ReadOptions) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let pointers = FilePageCellPointers::read(reader, file_header, page_number)?;
+        let cells = (0..pointers.offsets.len())
+            .map(|index| pointers.read_cell(reader, file_header, index, options))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let freeblocks = pointers.read_freeblocks(reader, file_header)?;
+
+        FilePage::from_cells(file_header, pointers, cells, freeblocks)
+    }
+
+    /// Reads a b-tree page of which only the first `length` bytes are in the file, keeping the
+    /// cells that end within them, see [`ReadOptions::partial_pages`]. The page may thus have
+    /// fewer cells than its header counts.
+    pub(crate) fn read_partial<R>(reader: &mut R, file_header: &FileHeader, page_number: u32, options: &ReadOptions, length: usize) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let mut pointers = FilePageCellPointers::read(reader, file_header, page_number)?;
+        let mut offsets = Vec::new();
+        let mut cells = Vec::new();
+
+        // Cells are stored from the end of the page, so the ones cut off come first in the content area
+        for (index, &offset) in pointers.offsets.iter().enumerate() {
+            if offset as usize >= length {
+                continue;
+            }
+            match pointers.read_cell(reader, file_header, index, options) {
+                Ok(cell) if offset as usize + cell.size as usize <= length => {
+                    offsets.push(offset);
+                    cells.push(cell);
+                }
+                _ => {}
+            }
+        }
+
+        let freeblocks = pointers.read_freeblocks(reader, file_header).unwrap_or_default();
+        pointers.offsets = offsets;
+
+        FilePage::from_cells(file_header, pointers, cells, freeblocks)
+    }
+
+    fn from_cells(file_header: &FileHeader, pointers: FilePageCellPointers, cells: Vec<FilePageCell>, freeblocks: Vec<(u16, u16)>) -> std::io::Result<Self> {
+        let page_number = pointers.page_number;
+
+        // Cells of a well-formed page never share bytes, which a corrupt cell pointer array may make them do
+        let mut extents = pointers.offsets.iter().zip(cells.iter())
+            .enumerate()
+            .map(|(index, (offset, cell))| (*offset as usize, *offset as usize + cell.size as usize, index))
+            .collect::<Vec<_>>();
+        extents.sort_unstable();
+
+        if let Some(pair) = extents.windows(2).find(|pair| pair[0].1 > pair[1].0) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Cells {} and {} of page {} overlap at offset {}", pair[0].2, pair[1].2, page_number, pair[1].0)));
+        }
+
+        Ok(FilePage {
+            page_number,
+            header: pointers.header,
+            cells,
+            freeblocks,
+            usable_size: file_header.usable_size(),
+        })
+    }
+
+    /// Right-most child pointer of an interior page, which leads to the keys after those of
+    /// its last cell. Leaf pages have none, and asking for it is an error.
+    pub fn right_most_pointer(&self) -> std::io::Result<u32> {
+        match (self.header.typ, self.header.right_most_pointer) {
+            (TableInterior | IndexInterior, Some(right_most_pointer)) => Ok(right_most_pointer),
+            (TableInterior | IndexInterior, None) => Err(Error::new(ErrorKind::InvalidData, format!("Interior page {} has no right-most pointer", self.page_number))),
+            (TableLeaf | IndexLeaf, _) => Err(Error::new(ErrorKind::InvalidData, format!("Leaf page {} has no right-most pointer", self.page_number))),
+        }
+    }
+
+    /// Byte range of the cell content area, from its start up to the reserved space.
+    pub fn content_range(&self) -> Range<usize> {
+        self.header.content_start()..self.usable_size as usize
+    }
+
+    /// Byte range of the unallocated space between the cell pointer array and the cell content area.
+    pub fn unallocated_range(&self) -> Range<usize> {
+        let pointers_end = FilePage::header_start(self.page_number) + self.header.size() + self.header.cells_count as usize * 2;
+        pointers_end..self.header.content_start()
+    }
+
+    /// Total number of bytes not used by cells: the unallocated space, the freeblocks, and the
+    /// fragmented bytes. Fragments of less than 4 bytes within the cell content area are too
+    /// small to become freeblocks, so the header only counts them in `cells_content_fragmented_bytes`.
+    pub fn free_bytes(&self) -> usize {
+        self.unallocated_range().len()
+            + self.freeblocks.iter().map(|(_, size)| *size as usize).sum::<usize>()
+            + self.header.cells_content_fragmented_bytes as usize
+    }
+
+    /// Offset of the b-tree header within the page, which is past the file header on page 1.
+    pub fn header_start(page_number: u32) -> usize {
+        if page_number == 1 { FILE_HEADER_SIZE } else { 0 }
+    }
+}
+
+/// The header and cell pointer array of a b-tree page, which allow reading its cells one
+/// at a time instead of all at once like [`FilePage::read`] does.
+#[derive(Debug)]
+pub struct FilePageCellPointers {
+    pub page_number: u32,
+    pub header: FilePageHeader,
+    /// Offsets of the cells from the start of the page, in key order.
+    pub offsets: Vec<u16>,
+}
+
+impl FilePageCellPointers {
+    pub fn read<R>(reader: &mut R, file_header: &FileHeader, page_number: u32) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        if page_number == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Page numbers start at 1"));
+        }
+
+        let header_start = FilePage::header_start(page_number);
+        let page_start = file_header.page_offset(page_number);
+        reader.seek(SeekFrom::Start(page_start + header_start as u64))?;
+
+        // A file that ends inside the header, such as before the right-most pointer of an interior page, is truncated
+        let header = match FilePageHeader::read(reader) {
+            Ok(header) => header,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                let file_length = reader.seek(SeekFrom::End(0))?;
+                return Err(Error::new(ErrorKind::InvalidData, if file_length <= page_start {
+                    format!("Page {} starts at offset {}, past the end of the file at {}", page_number, page_start, file_length)
+                } else {
+                    format!("Page {} is truncated: the file ends inside its header at {}", page_number, file_length)
+                }));
+            }
+            Err(err) => return Err(err),
+        };
+        header.validate(page_number, file_header.usable_size(), header_start)?;
+
+        let content_start = header.content_start() as u64;
+        let mut offsets = Vec::with_capacity(header.cells_count as usize);
+
+        for index in 0..header.cells_count {
+            let offset = reader.read_u16::<BigEndian>()?;
+
+            if offset as u64 >= file_header.usable_size() as u64 && (offset as u64) < file_header.page_size as u64 {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Cell {} of page {} starts at {}, inside the reserved space", index, page_number, offset)));
+            }
+
+            if (offset as u64) < content_start || offset as u64 >= file_header.usable_size() as u64 {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Cell {} of page {} starts at {}, outside of the cell content area", index, page_number, offset)));
+            }
+
+            offsets.push(offset);
+        }
+
+        Ok(FilePageCellPointers {
+            page_number,
+            header,
+            offsets,
+        })
+    }
+
+    /// Reads the cell with the given index, following its overflow chain if needed.
+    pub fn read_cell<R>(&self, reader: &mut R, file_header: &FileHeader, index: usize, options: &ReadOptions) -> std::io::Result<FilePageCell>
+        where R: Read + Seek
+    {
+        let offset = *self.offsets.get(index)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Page {} has no cell {}, only {}", self.page_number, index, self.offsets.len())))?;
+
+        reader.seek(SeekFrom::Start(file_header.page_offset(self.page_number) + offset as u64))?;
+        FilePageCell::read(reader, &self.header, file_header, options)
+    }
+
+    /// Reads the child page numbers of an interior page, in key order and ending with the
+    /// right-most pointer, without reading the rest of the cells. Leaf pages have no children.
+    pub fn read_child_pages<R>(&self, reader: &mut R, file_header: &FileHeader) -> std::io::Result<Vec<u32>>
+        where R: Read + Seek
+    {
+        let Some(right_most_pointer) = self.header.right_most_pointer else {
+            return Ok(Vec::new());
+        };
+
+        let mut children = Vec::with_capacity(self.offsets.len() + 1);
+
+        // The left child pointer is the first field of every interior cell
+        for offset in self.offsets.iter() {
+            reader.seek(SeekFrom::Start(file_header.page_offset(self.page_number) + *offset as u64))?;
+            children.push(file_header.check_child_page(self.page_number, reader.read_u32::<BigEndian>()?)?);
+        }
+
+        children.push(file_header.check_child_page(self.page_number, right_most_pointer)?);
+        Ok(children)
+    }
+
+    /// Follows the chain of freeblocks, returning the offset and size of each block.
+    pub fn read_freeblocks<R>(&self, reader: &mut R, file_header: &FileHeader) -> std::io::Result<Vec<(u16, u16)>>
+        where R: Read + Seek
+    {
+        let mut freeblocks = Vec::new();
+        let mut offset = self.header.first_free_block;
+
+        while offset != 0 {
+            reader.seek(SeekFrom::Start(file_header.page_offset(self.page_number) + offset as u64))?;
+            let next = reader.read_u16::<BigEndian>()?;
+            let size = reader.read_u16::<BigEndian>()?;
+
+            if size < 4 || offset as u32 + size as u32 > file_header.usable_size() {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Freeblock at {} of page {} has invalid size {}", offset, self.page_number, size)));
+            }
+
+            // Freeblocks are kept in ascending order and never adjacent, which also rules out cycles
+            if next != 0 && (next as u32) <= offset as u32 + size as u32 {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Freeblock at {} of page {} is followed by a freeblock at {}", offset, self.page_number, next)));
+            }
+
+            freeblocks.push((offset, size));
+            offset = next;
+        }
+
+        Ok(freeblocks)
+    }
+}
+
+#[derive(Debug)]
+pub struct FilePageCell {
+    /// Offset of the start of the cell within the database file. For pages read from a
+    /// write-ahead log, this is where the cell would be once the page is checkpointed.
+    pub file_offset: u64,
+    /// Number of bytes the cell takes up on its page, excluding the part of the payload in overflow pages.
+    pub size: u16,
+    /// Decoded payload, or `None` for table interior cells and if [`ReadOptions::decode_payload`] is disabled.
+    pub payload: Option<Record>,
+    /// Bytes of the payload, including the part stored in overflow pages, if
+    /// [`ReadOptions::keep_raw_payload`] is enabled.
+    pub raw_payload: Option<Vec<u8>>,
+    /// Length of the payload in bytes, including the part stored in overflow pages.
+    pub payload_length: Option<u64>,
+    pub left_child_page_number: Option<u32>,
+    pub first_overflow_page_number: Option<u32>,
+    pub rowid: Option<i64>,
+}
+
+impl FilePageCell {
+    pub fn read<R>(reader: &mut R, page_header: &FilePageHeader, file_header: &FileHeader, options: &ReadOptions) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let file_offset = reader.stream_position()?;
+
+        let left_child_page_number = match page_header.typ {
+            TableInterior | IndexInterior => Some(reader.read_u32::<BigEndian>()?),
+            _ => None
+        };
+
+        let payload_length = match page_header.typ {
+            TableLeaf | IndexLeaf | IndexInterior => Some(reader.read_var64()? as u64),
+            _ => None
+        };
+
+        // The key of a table interior cell follows the 4-byte child pointer and is a full 64-bit
+        // varint like the rowid of a leaf cell, so keys above 2^32 and negative keys are read as is
+        let rowid = match page_header.typ {
+            TableLeaf | TableInterior => Some(reader.read_var64()?),
+            _ => None
+        };
+
+        let (payload, raw_payload, first_overflow_page_number, cell_end) = match payload_length {
+            Some(payload_length) if options.decode_payload || options.keep_raw_payload => {
+                let (buf, first_overflow_page_number, cell_end) = FilePageCell::read_payload(reader, page_header, file_header, payload_length, true)?;
+                let record = if options.decode_payload {
+                    Some(Record::read(&mut Cursor::new(&buf), file_header, options).map_err(|err| match err.kind() {
+                        // The payload is already in memory, so running out of it means that the record header is corrupt
+                        ErrorKind::UnexpectedEof => Error::new(ErrorKind::InvalidData, format!("Record extends past the end of its payload of {} bytes", payload_length)),
+                        _ => err,
+                    })?)
+                } else {
+                    None
+                };
+                (record, options.keep_raw_payload.then_some(buf), first_overflow_page_number, cell_end)
+            }
+            Some(payload_length) => {
+                let (_, first_overflow_page_number, cell_end) = FilePageCell::read_payload(reader, page_header, file_header, payload_length, false)?;
+                (None, None, first_overflow_page_number, cell_end)
+            }
+            None => (None, None, None, reader.stream_position()?)
+        };
+
+        Ok(FilePageCell {
+            file_offset,
+            size: (cell_end - file_offset) as u16,
+            payload,
+            raw_payload,
+            payload_length,
+            left_child_page_number,
+            first_overflow_page_number,
+            rowid,
+        })
+    }
+
+    /// Reads the payload stored locally in the cell, followed by the chain of overflow pages
+    /// if the payload does not fit in the page. Returns the assembled payload, the number of
+    /// the first overflow page, if any, and the position of the end of the cell.
+    ///
+    /// This applies to the cells of index pages as well, interior ones included: their keys
+    /// spill to overflow pages just like table rows, only with a smaller local part.
+    ///
+    /// If `decode` is false, the payload is skipped instead and an empty one is returned,
+    /// leaving the reader at the end of the cell.
+    fn read_payload<R>(reader: &mut R, page_header: &FilePageHeader, file_header: &FileHeader, payload_length: u64, decode: bool) -> std::io::Result<(Vec<u8>, Option<u32>, u64)>
+        where R: Read + Seek
+    {
+        let usable_size = file_header.usable_size() as u64;
+        let local_length = local_payload_size(payload_length, file_header.usable_size(), page_header.typ);
+
+        // The local part of the payload and the overflow page number must end before the reserved space
+        let position = reader.stream_position()?;
+        let usable_end = (position & !(file_header.page_size as u64 - 1)) + usable_size;
+        let cell_end = position + local_length + if local_length < payload_length { 4 } else { 0 };
+
+        if cell_end > usable_end {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Cell payload of {} bytes extends {} bytes past the usable area of the page", payload_length, cell_end - usable_end)));
+        }
+
+        if !decode {
+            reader.seek(SeekFrom::Current(local_length as i64))?;
+            let first_overflow_page_number = if local_length < payload_length { Some(reader.read_u32::<BigEndian>()?) } else { None };
+            return Ok((Vec::new(), first_overflow_page_number, cell_end));
+        }
+
+        let mut payload = vec![0; local_length as usize];
+        reader.read_exact(&mut payload)?;
+
+        if local_length == payload_length {
+            return Ok((payload, None, cell_end));
+        }
+
+        let first_overflow_page_number = reader.read_u32::<BigEndian>()?;
+        let mut overflow_page_number = first_overflow_page_number;
+
+        // Each overflow page is used once, so a payload needing more pages than the database has is bogus
+        let overflow_pages = (payload_length - local_length).div_ceil(usable_size - 4);
+        if overflow_pages > file_header.database_size as u64 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Cell payload of {} bytes needs {} overflow pages, more than the {} pages of the database", payload_length, overflow_pages, file_header.database_size)));
+        }
+
+        let mut visited = HashSet::new();
+
+        while (payload.len() as u64) < payload_length {
+            if overflow_page_number == 0 {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Overflow chain ended {} bytes short of payload", payload_length - payload.len() as u64)));
+            }
+
+            if overflow_page_number > file_header.database_size {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Overflow page {} is outside of 1..={}", overflow_page_number, file_header.database_size)));
+            }
+
+            if !visited.insert(overflow_page_number) {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Overflow chain starting at page {} loops back to page {}", first_overflow_page_number, overflow_page_number)));
+            }
+
+            reader.seek(SeekFrom::Start(file_header.page_offset(overflow_page_number)))?;
+            overflow_page_number = reader.read_u32::<BigEndian>()?;
+
+            let chunk_length = (payload_length - payload.len() as u64).min(usable_size - 4);
+            let chunk_start = payload.len();
+            payload.resize(chunk_start + chunk_length as usize, 0);
+            reader.read_exact(&mut payload[chunk_start..])?;
+        }
+
+        Ok((payload, Some(first_overflow_page_number), cell_end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_header() -> FileHeader {
+        let mut data = vec![0; 100];
+        data[..16].copy_from_slice(b"SQLite format 3\0");
+        data[16..18].copy_from_slice(&4096u16.to_be_bytes());
+        data[56..60].copy_from_slice(&1u32.to_be_bytes());
+        FileHeader::read(&mut Cursor::new(data)).unwrap()
+    }
+
+    fn page_header(typ: FilePageType) -> FilePageHeader {
+        FilePageHeader {
+            typ,
+            first_free_block: 0,
+            cells_count: 1,
+            cells_content_start: 0,
+            cells_content_fragmented_bytes: 0,
+            right_most_pointer: Some(9),
+        }
+    }
+
+    /// Reads a table interior cell made of the child page 7 followed by the given varint key.
+    fn read_interior_cell(key: &[u8]) -> FilePageCell {
+        let mut bytes = 7u32.to_be_bytes().to_vec();
+        bytes.extend_from_slice(key);
+        let cell = FilePageCell::read(&mut Cursor::new(&bytes), &page_header(TableInterior), &file_header(), &ReadOptions::default()).unwrap();
+        assert_eq!(cell.size as usize, bytes.len());
+        cell
+    }
+
+    #[test]
+    fn interior_cell_keys_above_2_pow_32() {
+        let cell = read_interior_cell(&[0x90, 0x80, 0x80, 0x80, 0x01]);
+        assert_eq!(cell.left_child_page_number, Some(7));
+        assert_eq!(cell.rowid, Some((1 << 32) + 1));
+
+        let cell = read_interior_cell(&[0xa0, 0x80, 0x80, 0x80, 0x80, 0x00]);
+        assert_eq!(cell.left_child_page_number, Some(7));
+        assert_eq!(cell.rowid, Some(1 << 40));
+        assert!(cell.payload.is_none());
+    }
+
+    #[test]
+    fn interior_cell_keys_of_nine_bytes() {
+        let cell = read_interior_cell(&[0xbf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(cell.rowid, Some(i64::MAX));
+
+        let cell = read_interior_cell(&[0xff; 9]);
+        assert_eq!(cell.rowid, Some(-1));
+    }
+
+    /// Checks `local_payload_size` for pairs of a payload length and its expected local part.
+    fn assert_local_sizes(usable_size: u32, page_type: FilePageType, cases: &[(u64, u64)]) {
+        for &(payload_length, expected) in cases {
+            assert_eq!(local_payload_size(payload_length, usable_size, page_type), expected, "{} bytes in {} usable bytes", payload_length, usable_size);
+        }
+    }
+
+    #[test]
+    fn local_payload_of_table_leaves() {
+        // Up to the maximum of `usable - 35` bytes, then the minimum unless the rest fills
+        // whole overflow pages of `usable - 4` bytes, in which case up to the maximum again
+        assert_local_sizes(512, TableLeaf, &[(0, 0), (477, 477), (478, 39), (647, 139)]);
+        assert_local_sizes(4096, TableLeaf, &[(4061, 4061), (4062, 489), (4681, 589), (12245, 4061), (12246, 489)]);
+        assert_local_sizes(65536, TableLeaf, &[(65501, 65501), (65502, 8199), (74731, 9199)]);
+    }
+
+    #[test]
+    fn local_payload_of_index_pages() {
+        for page_type in [IndexLeaf, IndexInterior] {
+            assert_local_sizes(512, page_type, &[(102, 102), (103, 39), (610, 102), (611, 39)]);
+            assert_local_sizes(4096, page_type, &[(1002, 1002), (1003, 489), (1500, 489), (5094, 1002), (5095, 489)]);
+            assert_local_sizes(65536, page_type, &[(16422, 16422), (16423, 8199), (81954, 16422)]);
+        }
+    }
+
+    #[test]
+    fn local_payload_with_reserved_space() {
+        // Pages of 4096 bytes with 32 reserved, and of 65536 bytes with 255 reserved
+        assert_local_sizes(4064, TableLeaf, &[(4029, 4029), (4030, 485), (4555, 495)]);
+        assert_local_sizes(4064, IndexLeaf, &[(993, 993), (5053, 993), (5054, 485)]);
+        assert_local_sizes(65281, TableLeaf, &[(65246, 65246), (65247, 8167), (73449, 8172)]);
+        assert_local_sizes(65281, IndexInterior, &[(16358, 16358), (16359, 8167)]);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Seek};
+
+use crate::{FileHeader, FilePageType, Freelist, Pager, PtrmapEntry, PtrmapPageType};
+
+/// What a page of the database is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageKind {
+    /// A page of a table or index b-tree.
+    BTree,
+    /// A freelist trunk page, listing free leaf pages.
+    FreelistTrunk,
+    /// A free page listed by a trunk page.
+    FreelistLeaf,
+    /// A pointer-map page of an auto-vacuum database.
+    Ptrmap,
+    /// A page of an overflow chain holding the rest of a large payload.
+    Overflow,
+    /// The page holding the lock bytes at offset 2^30, which is left unused.
+    LockByte,
+    /// A page with only zero bytes, such as the pages at the end of a file that were allocated
+    /// but never written. The last page of an overflow chain holding zeros looks the same.
+    Unused,
+}
+
+/// A summary of a single page, classified without parsing its cells.
+#[derive(Debug, Clone)]
+pub struct PageInfo {
+    pub page_number: u32,
+    pub kind: PageKind,
+    /// Type of the b-tree page, or `None` for other kinds of pages.
+    pub page_type: Option<FilePageType>,
+    /// Number of cells on the b-tree page, or `None` for other kinds of pages.
+    pub cells_count: Option<u16>,
+}
+
+impl PageInfo {
+    /// Classifies a page. Overflow pages are only recorded in the pointer map of auto-vacuum
+    /// databases, otherwise a page that is neither free, nor a pointer-map page, nor a valid
+    /// b-tree page is assumed to be one, as that is the only kind of page left, unless it
+    /// holds only zeros.
+    pub fn read<R>(pager: &mut Pager<R>, page_number: u32) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let header = pager.header().clone();
+        if page_number == 0 || page_number > header.database_size() {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Page {} is outside of 1..={}", page_number, header.database_size())));
+        }
+
+        let freelist = Freelist::read(pager, &header)?;
+        let free_kind = if freelist.trunk_pages.contains(&page_number) {
+            Some(PageKind::FreelistTrunk)
+        } else if freelist.leaf_pages.contains(&page_number) {
+            Some(PageKind::FreelistLeaf)
+        } else {
+            None
+        };
+
+        PageInfo::classify(pager, &header, page_number, free_kind)
+    }
+
+    /// Classifies every page of the database, in page order, reading the freelist only once.
+    pub fn read_all<R>(pager: &mut Pager<R>) -> std::io::Result<Vec<Self>>
+        where R: Read + Seek
+    {
+        let header = pager.header().clone();
+        let free_kinds = PageInfo::free_kinds(pager)?;
+
+        (1..=header.database_size())
+            .map(|page_number| PageInfo::classify(pager, &header, page_number, free_kinds.get(&page_number).copied()))
+            .collect()
+    }
+
+    /// Reads the freelist, returning the kind of each page it lists.
+    pub(crate) fn free_kinds<R>(pager: &mut Pager<R>) -> std::io::Result<HashMap<u32, PageKind>>
+        where R: Read + Seek
+    {
+        let header = pager.header().clone();
+        let freelist = Freelist::read(pager, &header)?;
+
+        Ok(freelist.trunk_pages.iter().map(|&page_number| (page_number, PageKind::FreelistTrunk))
+            .chain(freelist.leaf_pages.iter().map(|&page_number| (page_number, PageKind::FreelistLeaf)))
+            .collect())
+    }
+
+    /// Classifies a page, given whether the freelist lists it as a trunk or a leaf page.
+    pub(crate) fn classify<R>(pager: &mut Pager<R>, header: &FileHeader, page_number: u32, free_kind: Option<PageKind>) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let other = |kind| PageInfo {
+            page_number,
+            kind,
+            page_type: None,
+            cells_count: None,
+        };
+
+        if header.is_ptrmap_page(page_number) {
+            return Ok(other(PageKind::Ptrmap));
+        }
+
+        if header.is_lock_byte_page(page_number) {
+            return Ok(other(PageKind::LockByte));
+        }
+
+        // Free pages keep their old content, so they must be ruled out before looking at it
+        if let Some(kind) = free_kind {
+            return Ok(other(kind));
+        }
+
+        if let Some(entry) = PtrmapEntry::read(pager, page_number)? {
+            if matches!(entry.page_type, PtrmapPageType::FirstOverflowPage | PtrmapPageType::OverflowPage) {
+                return Ok(other(PageKind::Overflow));
+            }
+        }
+
+        match pager.cell_pointers(page_number) {
+            Ok(pointers) => Ok(PageInfo {
+                page_number,
+                kind: PageKind::BTree,
+                page_type: Some(pointers.header.typ),
+                cells_count: Some(pointers.header.cells_count),
+            }),
+            // A zero page type is not a valid b-tree page, so only these pages can be all zeros
+            Err(err) if err.kind() == ErrorKind::InvalidData => match pager.read_raw(page_number) {
+                Ok(data) if data.iter().all(|&byte| byte == 0) => Ok(other(PageKind::Unused)),
+                _ => Ok(other(PageKind::Overflow)),
+            },
+            Err(err) => Err(err),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Seek};
+
+use crate::{FilePage, PageInfo, PageKind, Pager};
+
+/// Iterates over the b-tree pages of the database in file order, whatever table or index
+/// they belong to, see [`Database::pages`](crate::Database::pages).
+///
+/// Pages are read one at a time, bypassing the cache. Pages classified as anything other
+/// than b-tree pages by [`PageInfo`] are skipped. A page that cannot be parsed is yielded
+/// as an error and skipped as well, but iteration stops after any other error, such as
+/// the file ending early.
+pub struct PageIterator<'a, R> {
+    pager: &'a mut Pager<R>,
+    /// Kinds of the pages listed by the freelist, read on the first step.
+    free_kinds: Option<HashMap<u32, PageKind>>,
+    next_page_number: u32,
+    done: bool,
+}
+
+impl<'a, R> PageIterator<'a, R>
+    where R: Read + Seek
+{
+    pub fn new(pager: &'a mut Pager<R>) -> Self {
+        PageIterator {
+            pager,
+            free_kinds: None,
+            next_page_number: 1,
+            done: false,
+        }
+    }
+
+    fn advance(&mut self) -> std::io::Result<Option<(u32, FilePage)>> {
+        let header = self.pager.header().clone();
+
+        let free_kinds = match &self.free_kinds {
+            Some(free_kinds) => free_kinds,
+            None => {
+                let free_kinds = PageInfo::free_kinds(self.pager).inspect_err(|_| self.done = true)?;
+                self.free_kinds.insert(free_kinds)
+            }
+        };
+
+        while !self.done && self.next_page_number <= header.database_size() {
+            let page_number = self.next_page_number;
+            match page_number.checked_add(1) {
+                Some(next_page_number) => self.next_page_number = next_page_number,
+                None => self.done = true,
+            }
+
+            let info = PageInfo::classify(self.pager, &header, page_number, free_kinds.get(&page_number).copied())?;
+            if info.kind == PageKind::BTree {
+                return self.pager.read_page(page_number).map(|page| Some((page_number, page)));
+            }
+        }
+
+        self.done = true;
+        Ok(None)
+    }
+}
+
+impl<R> Iterator for PageIterator<'_, R>
+    where R: Read + Seek
+{
+    type Item = std::io::Result<(u32, FilePage)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.advance() {
+            Ok(page) => page.map(Ok),
+            Err(err) => {
+                if err.kind() != ErrorKind::InvalidData {
+                    self.done = true;
+                }
+                Some(Err(err))
+            }
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use std::rc::Rc;
+
+use crate::{DatabaseSizeSource, FileHeader, FilePage, FilePageCell, FilePageCellPointers, FilePageHeader, ReadOptions, Wal};
+use crate::wal::WalReader;
+
+/// Reads pages from the underlying reader on demand and keeps them cached.
+///
+/// The cache is unbounded by default. With [`Pager::with_cache_capacity`], the least recently
+/// used pages are evicted once it is full and read again when they are needed.
+///
+/// If a [`Wal`] is given, pages committed to it take precedence over the main file.
+#[derive(Debug)]
+pub struct Pager<R = File> {
+    reader: WalReader<R>,
+    header: FileHeader,
+    /// Length of the file, including any pages past its end that the WAL holds.
+    file_length: u64,
+    options: ReadOptions,
+    /// Pages read so far, keyed by their 1-based page number, with the time of their last use.
+    cache: HashMap<u32, (Rc<FilePage>, u64)>,
+    /// Page numbers of the cached pages, keyed by the time of their last use.
+    recently_used: BTreeMap<u64, u32>,
+    /// Maximum number of cached pages, or `None` if the cache is unbounded.
+    cache_capacity: Option<usize>,
+    /// Incremented on every access to the cache.
+    clock: u64,
+    stats: PagerStats,
+}
+
+/// The error returned for a page that the file ends in, or before, such as the last page of
+/// a file that was cut short while being copied. Errors of the pager for such pages have the
+/// [`ErrorKind::UnexpectedEof`] kind, and this as their inner error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncatedFile {
+    pub page_number: u32,
+    pub file_length: u64,
+    /// Number of bytes of the page past the end of the file.
+    pub missing_bytes: u64,
+}
+
+impl fmt::Display for TruncatedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "File is truncated: it ends at offset {}, {} bytes short of the end of page {}", self.file_length, self.missing_bytes, self.page_number)
+    }
+}
+
+impl std::error::Error for TruncatedFile {}
+
+/// Statistics of the page cache of a [`Pager`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PagerStats {
+    /// Number of pages returned from the cache.
+    pub hits: u64,
+    /// Number of pages that had to be read, either on first access or after being evicted.
+    pub misses: u64,
+    /// Number of pages evicted to keep the cache within its capacity.
+    pub evictions: u64,
+}
+
+impl<R> Pager<R>
+    where R: Read + Seek
+{
+    pub fn new(reader: R) -> std::io::Result<Self> {
+        Pager::from_reader(WalReader::new(reader, None))
+    }
+
+    pub fn with_wal(reader: R, wal: Wal) -> std::io::Result<Self> {
+        Pager::from_reader(WalReader::new(reader, Some(wal)))
+    }
+
+    fn from_reader(mut reader: WalReader<R>) -> std::io::Result<Self> {
+        let mut header = FileHeader::read(&mut reader)?;
+
+        if let Some(wal) = reader.wal() {
+            if wal.page_size() != header.page_size {
+                return Err(Error::new(ErrorKind::InvalidData, format!("WAL page size {} does not match database page size {}", wal.page_size(), header.page_size)));
+            }
+
+            if let Some(database_size) = wal.database_size() {
+                header.database_size = database_size;
+                header.database_size_source = DatabaseSizeSource::Wal;
+            }
+        }
+
+        let file_length = reader.seek(SeekFrom::End(0))?;
+
+        Ok(Pager {
+            reader,
+            header,
+            file_length,
+            options: ReadOptions::default(),
+            cache: HashMap::new(),
+            recently_used: BTreeMap::new(),
+            cache_capacity: None,
+            clock: 0,
+            stats: PagerStats::default(),
+        })
+    }
+
+    /// Limits the cache to `capacity` pages. A capacity of 0 disables caching.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.set_cache_capacity(Some(capacity));
+        self
+    }
+
+    /// Changes the maximum number of cached pages, evicting pages if there are more of them.
+    /// `None` makes the cache unbounded.
+    pub fn set_cache_capacity(&mut self, capacity: Option<usize>) {
+        self.cache_capacity = capacity;
+        self.evict(0);
+    }
+
+    pub fn cache_capacity(&self) -> Option<usize> {
+        self.cache_capacity
+    }
+
+    /// Returns the statistics of the page cache since the pager was created.
+    pub fn stats(&self) -> PagerStats {
+        self.stats
+    }
+
+    pub fn header(&self) -> &FileHeader {
+        &self.header
+    }
+
+    pub fn wal(&self) -> Option<&Wal> {
+        self.reader.wal()
+    }
+
+    pub fn read_options(&self) -> &ReadOptions {
+        &self.options
+    }
+
+    /// Changes how records are decoded. Pages read with the previous options are discarded.
+    pub fn set_read_options(&mut self, options: ReadOptions) {
+        self.options = options;
+        self.cache.clear();
+        self.recently_used.clear();
+    }
+
+    /// Reads the raw bytes of the page with the given 1-based number, bypassing the cache.
+    /// Unlike [`Pager::get`], this works for pages that are not b-tree pages.
+    pub fn read_raw(&mut self, page_number: u32) -> std::io::Result<Vec<u8>> {
+        if page_number == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Page numbers start at 1"));
+        }
+
+        self.check_complete(page_number)?;
+
+        let mut buf = vec![0; self.header.page_size as usize];
+        self.reader.seek(SeekFrom::Start(self.header.page_offset(page_number)))?;
+        self.reader.read_exact(&mut buf)?;
+
+        Ok(buf)
+    }
+
+    /// Returns the page with the given 1-based number, reading it on first access.
+    pub fn get(&mut self, page_number: u32) -> std::io::Result<Rc<FilePage>> {
+        self.clock += 1;
+
+        if let Some((page, last_used)) = self.cache.get_mut(&page_number) {
+            self.recently_used.remove(last_used);
+            self.recently_used.insert(self.clock, page_number);
+            *last_used = self.clock;
+            self.stats.hits += 1;
+            return Ok(page.clone());
+        }
+
+        self.stats.misses += 1;
+        let page = self.read_page(page_number)?;
+
+        Ok(self.insert(page))
+    }
+
+    /// Reads the page with the given 1-based number like [`Pager::get`], but bypassing the cache.
+    pub(crate) fn read_page(&mut self, page_number: u32) -> std::io::Result<FilePage> {
+        match self.check_complete(page_number) {
+            Ok(()) => FilePage::read(&mut self.reader, &self.header, page_number, &self.options),
+            Err(_) if self.options.partial_pages && self.file_length > self.header.page_offset(page_number) => {
+                let length = (self.file_length - self.header.page_offset(page_number)) as usize;
+                FilePage::read_partial(&mut self.reader, &self.header, page_number, &self.options, length)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Checks that the page with the given number ends before the end of the file, failing
+    /// with a [`TruncatedFile`] error otherwise.
+    fn check_complete(&self, page_number: u32) -> std::io::Result<()> {
+        let page_start = self.header.page_offset(page_number);
+        let page_end = page_start + self.header.page_size as u64;
+
+        if page_end > self.file_length {
+            return Err(Error::new(ErrorKind::UnexpectedEof, TruncatedFile {
+                page_number,
+                file_length: self.file_length,
+                missing_bytes: page_end - self.file_length.max(page_start),
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Puts a page into the cache as the most recently used one, replacing any cached page
+    /// with the same number. The page must have been read with the current read options.
+    pub(crate) fn insert(&mut self, page: FilePage) -> Rc<FilePage> {
+        let page_number = page.page_number;
+        let page = Rc::new(page);
+
+        if self.cache_capacity == Some(0) {
+            return page;
+        }
+
+        self.clock += 1;
+        if let Some((_, last_used)) = self.cache.remove(&page_number) {
+            self.recently_used.remove(&last_used);
+        }
+
+        self.evict(1);
+        self.cache.insert(page_number, (page.clone(), self.clock));
+        self.recently_used.insert(self.clock, page_number);
+
+        page
+    }
+
+    /// Evicts the least recently used pages until `reserve` more pages fit in the cache.
+    fn evict(&mut self, reserve: usize) {
+        let Some(capacity) = self.cache_capacity else {
+            return;
+        };
+
+        while self.cache.len() + reserve > capacity {
+            let Some((_, page_number)) = self.recently_used.pop_first() else {
+                break;
+            };
+            self.cache.remove(&page_number);
+            self.stats.evictions += 1;
+        }
+    }
+
+    /// Reads a single cell of a b-tree page without reading the rest of the page.
+    /// Like [`Pager::cells`], this bypasses the cache.
+    pub fn cell(&mut self, page_number: u32, index: usize) -> std::io::Result<FilePageCell> {
+        let pointers = FilePageCellPointers::read(&mut self.reader, &self.header, page_number)?;
+        pointers.read_cell(&mut self.reader, &self.header, index, &self.options)
+    }
+
+    /// Reads a single cell like [`Pager::cell`], but never its payload, whatever the read options.
+    pub(crate) fn cell_without_payload(&mut self, pointers: &FilePageCellPointers, index: usize) -> std::io::Result<FilePageCell> {
+        let options = ReadOptions {
+            decode_payload: false,
+            keep_raw_payload: false,
+            ..self.options
+        };
+        pointers.read_cell(&mut self.reader, &self.header, index, &options)
+    }
+
+    /// Reads the header and the cell pointer array of a b-tree page, bypassing the cache.
+    pub fn cell_pointers(&mut self, page_number: u32) -> std::io::Result<FilePageCellPointers> {
+        FilePageCellPointers::read(&mut self.reader, &self.header, page_number)
+    }
+
+    /// Reads the child page numbers of an interior page, see [`FilePageCellPointers::read_child_pages`].
+    pub fn child_pages(&mut self, pointers: &FilePageCellPointers) -> std::io::Result<Vec<u32>> {
+        pointers.read_child_pages(&mut self.reader, &self.header)
+    }
+
+    /// Iterates over the cells of a b-tree page, reading each one only when it is reached.
+    pub fn cells(&mut self, page_number: u32) -> std::io::Result<CellIterator<'_, R>> {
+        let pointers = FilePageCellPointers::read(&mut self.reader, &self.header, page_number)?;
+
+        Ok(CellIterator {
+            pager: self,
+            pointers,
+            index: 0,
+        })
+    }
+}
+
+/// Iterates over the cells of a single b-tree page, parsing them on demand.
+///
+/// Iteration stops after the first error.
+pub struct CellIterator<'a, R> {
+    pager: &'a mut Pager<R>,
+    pointers: FilePageCellPointers,
+    index: usize,
+}
+
+impl<R> CellIterator<'_, R> {
+    /// Header of the page being iterated over.
+    pub fn header(&self) -> &FilePageHeader {
+        &self.pointers.header
+    }
+}
+
+impl<R> Iterator for CellIterator<'_, R>
+    where R: Read + Seek
+{
+    type Item = std::io::Result<FilePageCell>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.pointers.offsets.len() {
+            return None;
+        }
+
+        let cell = self.pointers.read_cell(&mut self.pager.reader, &self.pager.header, self.index, &self.pager.options);
+        self.index = if cell.is_ok() { self.index + 1 } else { self.pointers.offsets.len() };
+
+        Some(cell)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.pointers.offsets.len() - self.index;
+        (0, Some(remaining))
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::{Cursor, ErrorKind, Read, Seek};
+
+use rayon::prelude::*;
+
+use crate::{Database, FilePage, PageInfo, PageKind, WalkSummary};
+
+impl<R> Database<R>
+    where R: Read + Seek
+{
+    /// Parses every b-tree page of the database on all cores and puts them into the page cache,
+    /// so that the b-trees can then be walked without reading or parsing any page.
+    ///
+    /// The threads cannot share the reader, so the pages are first read one by one into a copy
+    /// of the whole database in memory, from which each thread decodes its pages and follows
+    /// their overflow chains. With a bounded cache, only the last pages parsed are kept.
+    pub fn scan_parallel(&mut self) -> std::io::Result<WalkSummary> {
+        let pager = self.pager();
+        let header = pager.header().clone();
+        let options = *pager.read_options();
+
+        let page_numbers = PageInfo::read_all(pager)?.into_iter()
+            .filter(|info| info.kind == PageKind::BTree)
+            .map(|info| info.page_number)
+            .collect::<Vec<_>>();
+
+        // Pages past the end of a truncated file are left out, and fail to parse on their own
+        let mut data = Vec::with_capacity(usize::try_from(header.page_offset(header.database_size()) + header.page_size() as u64).unwrap_or(0));
+        for page_number in 1..=header.database_size() {
+            match pager.read_raw(page_number) {
+                Ok(page) => data.extend_from_slice(&page),
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let pages = page_numbers.par_iter()
+            .map(|&page_number| FilePage::read(&mut Cursor::new(data.as_slice()), &header, page_number, &options))
+            .collect::<Vec<_>>();
+
+        let mut summary = WalkSummary {
+            pages_visited: page_numbers.len(),
+            errors: Vec::new(),
+            stopped: false,
+        };
+
+        for page in pages {
+            match page {
+                Ok(page) => {
+                    pager.insert(page);
+                }
+                Err(err) => summary.errors.push(err),
+            }
+        }
+
+        Ok(summary)
+    }
+}
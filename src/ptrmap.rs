@@ -0,0 +1,65 @@
+use std::io::{Error, ErrorKind, Read, Seek};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::Pager;
+
+/// What a page listed in a pointer map is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtrmapPageType {
+    /// A root b-tree page, which has no parent.
+    RootPage,
+    /// A freelist page, which has no parent.
+    FreePage,
+    /// The first page of an overflow chain, whose parent is the b-tree page holding the cell.
+    FirstOverflowPage,
+    /// A later page of an overflow chain, whose parent is the previous overflow page.
+    OverflowPage,
+    /// A non-root b-tree page, whose parent is its parent b-tree page.
+    BtreePage,
+}
+
+/// An entry of a pointer-map page, which auto-vacuum databases keep for every page after
+/// the first so that pages can be relocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtrmapEntry {
+    pub page_type: PtrmapPageType,
+    /// Page number of the parent page, or 0 for root and freelist pages.
+    pub parent: u32,
+}
+
+impl PtrmapEntry {
+    /// Reads the pointer-map entry of a page. Returns `None` if the database has no pointer
+    /// map or the page has no entry, as is the case for page 1, pointer-map pages and the
+    /// lock-byte page.
+    pub fn read<R>(pager: &mut Pager<R>, page_number: u32) -> std::io::Result<Option<Self>>
+        where R: Read + Seek
+    {
+        let header = pager.header();
+        if !header.is_autovacuum() || page_number < 2 || header.is_ptrmap_page(page_number) || header.is_lock_byte_page(page_number) {
+            return Ok(None);
+        }
+
+        let ptrmap_page = header.ptrmap_page_of(page_number);
+        let offset = 5 * (page_number - ptrmap_page - 1) as usize;
+
+        let data = pager.read_raw(ptrmap_page)?;
+        PtrmapEntry::parse(&data[offset..offset + 5]).map(Some)
+    }
+
+    fn parse(data: &[u8]) -> std::io::Result<Self> {
+        let page_type = match data[0] {
+            1 => PtrmapPageType::RootPage,
+            2 => PtrmapPageType::FreePage,
+            3 => PtrmapPageType::FirstOverflowPage,
+            4 => PtrmapPageType::OverflowPage,
+            5 => PtrmapPageType::BtreePage,
+            x => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown pointer-map page type: {}", x))),
+        };
+
+        Ok(PtrmapEntry {
+            page_type,
+            parent: BigEndian::read_u32(&data[1..5]),
+        })
+    }
+}
@@ -0,0 +1,633 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{Cursor, Error, ErrorKind, Read, Seek, SeekFrom};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
+
+use crate::{FileHeader, TableDef};
+use crate::csv::hex;
+use crate::recovery::plausible_record_length;
+use crate::varint::ReadVarExt;
+
+/// Options controlling how records are decoded.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOptions {
+    /// Replace invalid text with U+FFFD instead of failing, as real-world databases may
+    /// contain text columns with bytes that are not valid in the database's encoding.
+    pub lossy_text: bool,
+    /// Decode the payload of each cell into a [`Record`]. If disabled, cells only carry the
+    /// length of their payload and their first overflow page, which is enough for structural
+    /// traversals and much faster, as neither the payload nor its overflow pages are read.
+    pub decode_payload: bool,
+    /// Decode text as UTF-8 if the header holds an unknown text encoding, such as the 0 of some
+    /// malformed or very old files, instead of failing on every text value.
+    pub unknown_encoding_as_utf8: bool,
+    /// Keep the bytes of each payload in [`FilePageCell::raw_payload`](crate::FilePageCell::raw_payload),
+    /// to hash or compare records byte for byte. Disabled by default to save the allocation.
+    pub keep_raw_payload: bool,
+    /// How to read values of the reserved serial types 10 and 11, which SQLite never writes.
+    pub unknown_serial_types: UnknownSerialTypes,
+    /// Parse the cells that fit on a b-tree page the file ends in, leaving out the cells that
+    /// are not entirely in the file, instead of failing with a [`TruncatedFile`](crate::TruncatedFile) error.
+    pub partial_pages: bool,
+}
+
+/// How [`Record::read`] handles values of the reserved serial types 10 and 11, which may
+/// show up in damaged files, see [`ReadOptions::unknown_serial_types`]. The type of such
+/// a value can still be looked up with [`Record::serial_type`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownSerialTypes {
+    /// Fail to read the record.
+    #[default]
+    Error,
+    /// Read the value as NULL, stored in no bytes, as SQLite itself does.
+    Null,
+    /// Read the value as a blob of the bytes of the record not taken by its other values.
+    /// The length of a reserved type is unknown, so this is all of them for the first such
+    /// value of a record, and none for the ones after it.
+    Raw,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions {
+            lossy_text: false,
+            decode_payload: true,
+            unknown_encoding_as_utf8: false,
+            keep_raw_payload: false,
+            unknown_serial_types: UnknownSerialTypes::Error,
+            partial_pages: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RecordEntry {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Blob(Vec<u8>),
+    Text(String),
+}
+
+impl RecordEntry {
+    pub fn is_null(&self) -> bool {
+        matches!(self, RecordEntry::Null)
+    }
+
+    /// Returns the value of an integer entry.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            RecordEntry::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a numeric entry, converting integers to floating point.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            RecordEntry::Integer(value) => Some(*value as f64),
+            RecordEntry::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a text entry.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RecordEntry::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a blob entry.
+    pub fn as_blob(&self) -> Option<&[u8]> {
+        match self {
+            RecordEntry::Blob(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Decodes a blob entry as if it held a record of its own, as some applications store
+    /// serialized rows in blobs. Text is decoded in the encoding of the database.
+    ///
+    /// This is a best-effort reinterpretation: it fails if the blob is not exactly one
+    /// well-formed record, but a blob that happens to look like one decodes into garbage.
+    pub fn as_nested_record(&self, file_header: &FileHeader) -> std::io::Result<Record> {
+        let RecordEntry::Blob(value) = self else {
+            return Err(Error::new(ErrorKind::InvalidInput, "Only blobs can hold a nested record"));
+        };
+
+        // The lengths are checked first, so that a bogus header never makes the record allocate its body
+        if plausible_record_length(value) != Some(value.len()) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Blob of {} bytes does not hold a record", value.len())));
+        }
+
+        Record::read(&mut Cursor::new(value), file_header, &ReadOptions::default())
+    }
+}
+
+/// Renders the entry the way the `sqlite3` shell does: NULL as nothing, text without quotes
+/// and blobs as `x'...'` literals. Floats render with 15 significant digits, like the `%!.15g`
+/// format of SQLite, NaN as nothing and infinities as `Inf` and `-Inf`.
+///
+/// A precision, as in `{:.20}`, cuts text and blobs longer than that many characters short
+/// with an ellipsis, followed by their full length.
+impl fmt::Display for RecordEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordEntry::Null => Ok(()),
+            RecordEntry::Integer(value) => write!(f, "{}", value),
+            RecordEntry::Float(value) => f.write_str(&float_text(*value)),
+            RecordEntry::Blob(value) => match (f.precision(), hex(value)) {
+                (Some(max_width), hex) if hex.len() > max_width => write!(f, "x'{}…' ({} bytes)", &hex[..max_width], value.len()),
+                (_, hex) => write!(f, "x'{}'", hex),
+            },
+            RecordEntry::Text(value) => match f.precision() {
+                Some(max_width) if value.chars().count() > max_width => {
+                    write!(f, "{}… ({} chars)", value.chars().take(max_width).collect::<String>(), value.chars().count())
+                }
+                _ => f.write_str(value),
+            },
+        }
+    }
+}
+
+/// Renders a float the way the `sqlite3` shell does, with the `%!.15g` format of SQLite:
+/// 15 significant digits, without trailing zeros but with a `.0` for integral values, and
+/// an exponent of at least two digits, as in `1.0e+20`, below 1e-4 and from 1e15 on.
+///
+/// SQLite never stores NaN, which it turns into NULL on insert, so a NaN written by another
+/// tool renders like NULL, as nothing. Infinities render as `Inf` and `-Inf`.
+pub(crate) fn float_text(value: f64) -> String {
+    match value {
+        x if x.is_nan() => String::new(),
+        f64::INFINITY => "Inf".to_string(),
+        f64::NEG_INFINITY => "-Inf".to_string(),
+        x => {
+            // Rounded to 15 significant digits, as in "1.23456789012346e17"
+            let scientific = format!("{:.14e}", x);
+            let (mantissa, exponent) = scientific.split_once('e').unwrap();
+            let exponent = exponent.parse::<i32>().unwrap();
+            let (sign, mantissa) = match mantissa.strip_prefix('-') {
+                Some(mantissa) => ("-", mantissa),
+                None => ("", mantissa),
+            };
+            let digits = mantissa.replace('.', "");
+
+            let (integral, fraction) = if (-4..15).contains(&exponent) {
+                match exponent {
+                    0.. => (digits[..exponent as usize + 1].to_string(), digits[exponent as usize + 1..].to_string()),
+                    _ => ("0".to_string(), "0".repeat(-exponent as usize - 1) + &digits),
+                }
+            } else {
+                (digits[..1].to_string(), digits[1..].to_string())
+            };
+
+            let fraction = match fraction.trim_end_matches('0') {
+                "" => "0",
+                fraction => fraction,
+            };
+
+            if (-4..15).contains(&exponent) {
+                format!("{}{}.{}", sign, integral, fraction)
+            } else {
+                format!("{}{}.{}e{}{:02}", sign, integral, fraction, if exponent < 0 { '-' } else { '+' }, exponent.abs())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub entries: Vec<RecordEntry>,
+    /// Serial type each entry was stored with, see [`Record::serial_type`]. Empty for records
+    /// that were not read from the database.
+    pub serial_types: Vec<u64>,
+}
+
+impl Record {
+    /// Reads a record from its header up to the end of the reader at most.
+    ///
+    /// The lengths of the values declared by the header are checked against the bytes left in
+    /// the reader before any value is read, so that a corrupt header fails with the column at
+    /// fault instead of running out of bytes halfway through, or allocating a bogus length.
+    pub fn read<R>(reader: &mut R, file_header: &FileHeader, options: &ReadOptions) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let record_start = reader.stream_position()?;
+        let record_size = reader.read_var64()? as u64;
+
+        // The header size counts its own varint, so a header of a single byte declares no columns.
+        // Like SQLite, a size smaller than that of its varint is read as no columns too
+        let types_start = reader.stream_position()?;
+        let data_end = reader.seek(SeekFrom::End(0))?;
+        let record_end = match record_start.checked_add(record_size) {
+            Some(record_end) if record_end <= data_end => record_end,
+            _ => return Err(Error::new(ErrorKind::InvalidData, format!("Record header of {} bytes extends past the end of the record at {} bytes", record_size, data_end - record_start))),
+        };
+        reader.seek(SeekFrom::Start(types_start))?;
+
+        let mut entry_types = Vec::new();
+        let mut entries = Vec::new();
+
+        while reader.stream_position()? < record_end {
+            entry_types.push(reader.read_var64()? as u64);
+        }
+
+        let body_start = reader.stream_position()?;
+        let mut remaining = data_end.saturating_sub(body_start);
+
+        let mut lengths = Vec::with_capacity(entry_types.len());
+        for (index, typ) in entry_types.iter().enumerate() {
+            let length = match (serial_type_length(*typ), options.unknown_serial_types) {
+                (Some(length), _) => length,
+                (None, UnknownSerialTypes::Error) => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown record type: {}", typ))),
+                (None, _) => 0,
+            };
+            if length > remaining {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Record header declares {} bytes for column {} but only {} remain", length, index, remaining)));
+            }
+            remaining -= length;
+            lengths.push(length);
+        }
+
+        if options.unknown_serial_types == UnknownSerialTypes::Raw {
+            if let Some(index) = entry_types.iter().position(|&typ| serial_type_length(typ).is_none()) {
+                lengths[index] = remaining;
+            }
+        }
+
+        for (typ, length) in entry_types.iter().zip(lengths) {
+            entries.push(match *typ {
+                0 => RecordEntry::Null,
+                1 => RecordEntry::Integer(reader.read_i8()? as i64),
+                2 => RecordEntry::Integer(reader.read_i16::<BigEndian>()? as i64),
+                3 => RecordEntry::Integer(reader.read_i24::<BigEndian>()? as i64),
+                4 => RecordEntry::Integer(reader.read_i32::<BigEndian>()? as i64),
+                5 => RecordEntry::Integer(reader.read_i48::<BigEndian>()?),
+                6 => RecordEntry::Integer(reader.read_i64::<BigEndian>()?),
+                7 => RecordEntry::Float(reader.read_f64::<BigEndian>()?),
+                8 => RecordEntry::Integer(0),
+                9 => RecordEntry::Integer(1),
+                x if x >= 12 && x % 2 == 0 => {
+                    let mut buf = vec![0; ((x - 12) / 2) as usize];
+                    reader.read_exact(&mut buf)?;
+                    RecordEntry::Blob(buf)
+                }
+                x if x >= 13 && x % 2 == 1 => {
+                    let mut buf = vec![0; ((x - 13) / 2) as usize];
+                    reader.read_exact(&mut buf)?;
+                    RecordEntry::Text(Record::decode_text(buf, file_header.text_encoding, options)?)
+                }
+                _ if options.unknown_serial_types == UnknownSerialTypes::Raw => {
+                    let mut buf = vec![0; length as usize];
+                    reader.read_exact(&mut buf)?;
+                    RecordEntry::Blob(buf)
+                }
+                _ => RecordEntry::Null,
+            })
+        }
+
+        Ok(Record {
+            entries,
+            serial_types: entry_types,
+        })
+    }
+
+    /// Returns the serial type the entry at `index` was stored with, which tells apart the
+    /// integers that [`RecordEntry::Integer`] does not: types 1 to 6 are integers of 1, 2, 3,
+    /// 4, 6 and 8 bytes, and types 8 and 9 are the constants 0 and 1, stored in no bytes.
+    pub fn serial_type(&self, index: usize) -> Option<u64> {
+        self.serial_types.get(index).copied()
+    }
+
+    /// Number of entries in the record. Rows written before an `ALTER TABLE ADD COLUMN` have
+    /// fewer entries than the table has columns.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entry at `index`, or `None` if the record has no such entry.
+    pub fn get(&self, index: usize) -> Option<&RecordEntry> {
+        self.entries.get(index)
+    }
+
+    fn decode_text(buf: Vec<u8>, text_encoding: u32, options: &ReadOptions) -> std::io::Result<String> {
+        let invalid = |buf: &[u8], encoding: &str| Error::new(ErrorKind::InvalidData, format!("Invalid {} text: x'{}'", encoding, hex(buf)));
+        let lossy = options.lossy_text;
+
+        let text_encoding = match text_encoding {
+            1..=3 => text_encoding,
+            _ if options.unknown_encoding_as_utf8 => 1,
+            x => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown text encoding in the database header: {}, expected 1 (UTF-8), 2 (UTF-16le) or 3 (UTF-16be)", x))),
+        };
+
+        let units = match text_encoding {
+            1 if lossy => return Ok(String::from_utf8_lossy(&buf).into_owned()),
+            1 => return String::from_utf8(buf).map_err(|err| invalid(err.as_bytes(), "UTF-8")),
+            2 | 3 if !lossy && !buf.len().is_multiple_of(2) => return Err(invalid(&buf, "UTF-16")),
+            2 => buf.chunks_exact(2).map(LittleEndian::read_u16).collect::<Vec<_>>(),
+            _ => buf.chunks_exact(2).map(BigEndian::read_u16).collect::<Vec<_>>(),
+        };
+
+        if lossy {
+            // A trailing odd byte is not a whole code unit and decodes as a replacement character
+            let mut text = String::from_utf16_lossy(&units);
+            if !buf.len().is_multiple_of(2) {
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+            return Ok(text);
+        }
+
+        String::from_utf16(&units).map_err(|_| invalid(&buf, "UTF-16"))
+    }
+
+    /// Pairs the entries of this record with the columns of the given table.
+    pub fn with_columns<'a>(&'a self, table: &'a TableDef) -> RecordView<'a> {
+        RecordView {
+            table,
+            record: self,
+        }
+    }
+}
+
+/// Length of the body of a value with the given serial type, or `None` for reserved types.
+pub(crate) fn serial_type_length(serial_type: u64) -> Option<u64> {
+    match serial_type {
+        x @ 0..=4 => Some(x),
+        5 => Some(6),
+        6 | 7 => Some(8),
+        8 | 9 => Some(0),
+        x if x >= 12 => Some((x - 12) / 2),
+        _ => None,
+    }
+}
+
+/// Renders the entries separated by `|`, as in the list mode of the `sqlite3` shell.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, entry) in self.entries.iter().enumerate() {
+            if index > 0 {
+                f.write_str("|")?;
+            }
+            match f.precision() {
+                Some(max_width) => write!(f, "{:.*}", max_width, entry)?,
+                None => write!(f, "{}", entry)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A record viewed through the definition of the table it belongs to.
+pub struct RecordView<'a> {
+    pub table: &'a TableDef,
+    pub record: &'a Record,
+}
+
+impl<'a> RecordView<'a> {
+    /// Iterates over column name and entry pairs. Entries beyond the declared columns are skipped,
+    /// and columns missing from the record, as in rows written before an `ALTER TABLE ADD COLUMN`,
+    /// come with their default value, see [`ColumnDef::default_value`](crate::ColumnDef::default_value).
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, Cow<'a, RecordEntry>)> {
+        let entries = self.record.entries.as_slice();
+
+        self.table.columns.iter()
+            .enumerate()
+            .map(move |(index, column)| match entries.get(index) {
+                Some(entry) => (column.name.as_str(), Cow::Borrowed(entry)),
+                None => (column.name.as_str(), Cow::Owned(column.default_value().unwrap_or(RecordEntry::Null))),
+            })
+    }
+
+    /// Returns the entry of the column with the given name, ignoring case as SQLite does, or
+    /// `None` if the table has no such column. A column missing from the record comes with its
+    /// default value, as in [`RecordView::iter`].
+    pub fn get_by_name(&self, name: &str) -> Option<Cow<'a, RecordEntry>> {
+        let index = self.table.column_index(name)?;
+
+        Some(match self.record.get(index) {
+            Some(entry) => Cow::Borrowed(entry),
+            None => Cow::Owned(self.table.columns[index].default_value().unwrap_or(RecordEntry::Null)),
+        })
+    }
+}
+
+impl fmt::Debug for RecordView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn file_header(text_encoding: u32) -> FileHeader {
+        let mut data = vec![0; 100];
+        data[..16].copy_from_slice(b"SQLite format 3\0");
+        data[16..18].copy_from_slice(&4096u16.to_be_bytes());
+        data[56..60].copy_from_slice(&text_encoding.to_be_bytes());
+        FileHeader::read(&mut Cursor::new(data)).unwrap()
+    }
+
+    /// Builds a record from the serial types of its header, each taking a single byte, and its body.
+    fn record_bytes(serial_types: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![serial_types.len() as u8 + 1];
+        bytes.extend_from_slice(serial_types);
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    fn read(bytes: &[u8], options: &ReadOptions) -> std::io::Result<Record> {
+        Record::read(&mut Cursor::new(bytes), &file_header(1), options)
+    }
+
+    #[test]
+    fn renders_rows_like_the_sqlite3_shell() {
+        let record = Record {
+            entries: vec![
+                RecordEntry::Integer(1),
+                RecordEntry::Text("Alice".to_string()),
+                RecordEntry::Float(2.5),
+                RecordEntry::Null,
+                RecordEntry::Blob(vec![0xde, 0xad]),
+                RecordEntry::Float(3.0),
+                RecordEntry::Integer(-7),
+            ],
+            serial_types: Vec::new(),
+        };
+
+        assert_eq!(record.to_string(), "1|Alice|2.5||x'dead'|3.0|-7");
+    }
+
+    #[test]
+    fn renders_floats_like_the_sqlite3_shell() {
+        // Output of `sqlite3 :memory: "SELECT ..."` for each value
+        let cases = [
+            (0.1 + 0.2, "0.3"),
+            (1e20, "1.0e+20"),
+            (1.5e-7, "1.5e-07"),
+            (123456789012345678.0, "1.23456789012346e+17"),
+            (-2.5, "-2.5"),
+            (0.0, "0.0"),
+            (100.0, "100.0"),
+            (1e14, "100000000000000.0"),
+            (1e15, "1.0e+15"),
+            (0.0001, "0.0001"),
+            (0.00001, "1.0e-05"),
+            (1e-300, "1.0e-300"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(RecordEntry::Float(value).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn precision_cuts_long_text_and_blobs_short() {
+        assert_eq!(format!("{:.3}", RecordEntry::Text("Alice".to_string())), "Ali… (5 chars)");
+        assert_eq!(format!("{:.3}", RecordEntry::Text("Bob".to_string())), "Bob");
+        assert_eq!(format!("{:.4}", RecordEntry::Blob(vec![0xde, 0xad, 0xbe, 0xef])), "x'dead…' (4 bytes)");
+    }
+
+    #[test]
+    fn invalid_utf8_text_is_an_error() {
+        let bytes = record_bytes(&[19], b"f\xffo");
+
+        let err = read(&bytes, &ReadOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Invalid UTF-8 text: x'66ff6f'");
+    }
+
+    #[test]
+    fn invalid_utf8_text_is_replaced_in_lossy_mode() {
+        let bytes = record_bytes(&[19, 1], b"f\xffo\x05");
+        let options = ReadOptions {
+            lossy_text: true,
+            ..ReadOptions::default()
+        };
+
+        let record = read(&bytes, &options).unwrap();
+        assert_eq!(record.entries[0].as_str(), Some("f\u{fffd}o"));
+        assert_eq!(record.entries[1].as_i64(), Some(5));
+    }
+
+    #[test]
+    fn nan_renders_like_null() {
+        // A quiet NaN with a payload, as another tool may have written it
+        let mut body = 0x7ff8_0000_0000_0001u64.to_be_bytes().to_vec();
+        body.extend_from_slice(&f64::INFINITY.to_be_bytes());
+        body.extend_from_slice(&f64::NEG_INFINITY.to_be_bytes());
+        let record = read(&record_bytes(&[7, 7, 7], &body), &ReadOptions::default()).unwrap();
+
+        let [nan, infinity, negative_infinity] = record.entries.as_slice() else {
+            panic!("expected 3 entries, got {:?}", record.entries);
+        };
+        assert!(matches!(nan, RecordEntry::Float(x) if x.is_nan()));
+
+        assert_eq!(record.to_string(), "|Inf|-Inf");
+        assert_eq!(nan.to_json(), "null");
+        assert_eq!(infinity.to_json(), "9e999");
+        assert_eq!(negative_infinity.to_json(), "-9e999");
+        assert_eq!(crate::csv::csv_field(nan), "");
+        assert_eq!(crate::csv::csv_field(infinity), "Inf");
+    }
+
+    #[test]
+    fn lengths_past_the_end_of_the_record_are_errors() {
+        // An integer, 3 bytes of text and an 8-byte integer, of which only 2 bytes are left
+        let bytes = record_bytes(&[1, 19, 6], b"\x05abc\x00\x01");
+
+        let err = read(&bytes, &ReadOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Record header declares 8 bytes for column 2 but only 2 remain");
+
+        // A blob of 94 bytes, whose serial type takes two bytes, with only 10 bytes after the header
+        let mut bytes = vec![3, 0x81, 0x48];
+        bytes.extend_from_slice(&[0; 10]);
+        let err = read(&bytes, &ReadOptions::default()).unwrap_err();
+        assert_eq!(err.to_string(), "Record header declares 94 bytes for column 0 but only 10 remain");
+
+        bytes.extend_from_slice(&[0; 84]);
+        assert_eq!(read(&bytes, &ReadOptions::default()).unwrap().entries[0].as_blob().map(<[u8]>::len), Some(94));
+    }
+
+    #[test]
+    fn reserved_serial_types_are_an_error_by_default() {
+        let bytes = record_bytes(&[1, 10, 15], b"\x05x");
+
+        let err = read(&bytes, &ReadOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Unknown record type: 10");
+    }
+
+    #[test]
+    fn reserved_serial_types_read_as_null() {
+        let options = ReadOptions {
+            unknown_serial_types: UnknownSerialTypes::Null,
+            ..ReadOptions::default()
+        };
+
+        let record = read(&record_bytes(&[1, 10, 15, 11], b"\x05x"), &options).unwrap();
+        assert_eq!(record.to_string(), "5||x|");
+        assert_eq!(record.serial_type(1), Some(10));
+        assert_eq!(record.serial_type(3), Some(11));
+    }
+
+    #[test]
+    fn reserved_serial_types_read_as_raw_bytes() {
+        let options = ReadOptions {
+            unknown_serial_types: UnknownSerialTypes::Raw,
+            ..ReadOptions::default()
+        };
+
+        // The bytes not taken by the integer and the text belong to the first reserved type
+        let record = read(&record_bytes(&[1, 10, 15, 11], b"\x05\xde\xadx"), &options).unwrap();
+        assert_eq!(record.entries[0].as_i64(), Some(5));
+        assert_eq!(record.entries[1].as_blob(), Some(&[0xde, 0xad][..]));
+        assert_eq!(record.entries[2].as_str(), Some("x"));
+        assert_eq!(record.entries[3].as_blob(), Some(&[][..]));
+    }
+
+    #[test]
+    fn header_of_a_single_byte_declares_no_columns() {
+        let record = read(&record_bytes(&[], &[]), &ReadOptions::default()).unwrap();
+        assert!(record.is_empty());
+        assert!(record.serial_types.is_empty());
+        assert_eq!(record.to_string(), "");
+
+        // A header size of 0, less than the byte it takes, is read the same way
+        assert!(read(&[0], &ReadOptions::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn header_past_the_end_of_the_record_is_an_error() {
+        let err = read(&[5, 1], &ReadOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Record header of 5 bytes extends past the end of the record at 2 bytes");
+
+        // A header that ends exactly where the record does holds nothing but serial types
+        let record = read(&[3, 8, 9], &ReadOptions::default()).unwrap();
+        assert_eq!(record.to_string(), "0|1");
+    }
+
+    #[test]
+    fn header_size_overflowing_the_offset_is_an_error() {
+        // A record one byte into the stream, whose header size is the largest varint
+        let mut bytes = vec![0];
+        bytes.extend_from_slice(&[0xff; 9]);
+        let mut reader = Cursor::new(bytes);
+        reader.set_position(1);
+        let err = Record::read(&mut reader, &file_header(1), &ReadOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), format!("Record header of {} bytes extends past the end of the record at 9 bytes", u64::MAX));
+    }
+}
@@ -0,0 +1,199 @@
+use std::io::{Cursor, Read, Seek};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{FileHeader, FilePage, FilePageHeader, Pager, ReadOptions, Record, RecordEntry};
+use crate::record::serial_type_length;
+use crate::varint::ReadVarExt;
+
+/// Attempts to recover records of deleted cells from a b-tree page.
+///
+/// When a cell is deleted, its bytes either become part of a freeblock or of the unallocated
+/// gap between the cell pointer array and the cell content area, and stay there until they
+/// are overwritten. Freeblocks are decoded by rebuilding the record header that the freeblock
+/// header overwrote, and any remaining space is scanned byte by byte for intact records.
+/// This is a heuristic: the rowid of a deleted cell is lost, and unrelated bytes may
+/// occasionally decode as a record too.
+pub fn recover_deleted<R>(pager: &mut Pager<R>, page_number: u32) -> std::io::Result<Vec<Record>>
+    where R: Read + Seek
+{
+    let header = pager.header().clone();
+    let page = pager.get(page_number)?;
+    let mut data = pager.read_raw(page_number)?;
+    let mut records = Vec::new();
+
+    // The reserved space at the end of the page never holds cell content
+    data.truncate(header.usable_size() as usize);
+
+    for (start, end) in freeblocks(&data, &page.header) {
+        match rebuild_freeblock_span(&data, start, end, &header) {
+            Some(rebuilt) => records.extend(rebuilt),
+            None => records.extend(scan_records(&data[start + 4..end], &header)),
+        }
+    }
+
+    if let Some((start, end)) = unallocated_region(&data, page_number, &page.header) {
+        records.extend(scan_records(&data[start..end], &header));
+    }
+
+    Ok(records)
+}
+
+/// Byte range between the end of the cell pointer array and the start of the cell content area.
+fn unallocated_region(data: &[u8], page_number: u32, header: &FilePageHeader) -> Option<(usize, usize)> {
+    let header_start = FilePage::header_start(page_number);
+    let pointers_end = header_start + header.size() + header.cells_count as usize * 2;
+    let content_start = header.content_start().min(data.len());
+
+    if pointers_end < content_start { Some((pointers_end, content_start)) } else { None }
+}
+
+/// Byte ranges of the freeblocks of a page, including their 4-byte headers.
+fn freeblocks(data: &[u8], header: &FilePageHeader) -> Vec<(usize, usize)> {
+    let mut freeblocks = Vec::new();
+    let mut freeblock = header.first_free_block as usize;
+
+    // Freeblocks are kept in ascending order, which also guards against cycles
+    while freeblock != 0 && freeblock + 4 <= data.len() {
+        let next = BigEndian::read_u16(&data[freeblock..]) as usize;
+        let size = BigEndian::read_u16(&data[freeblock + 2..]) as usize;
+        let end = (freeblock + size).min(data.len());
+
+        if freeblock + 4 < end {
+            freeblocks.push((freeblock, end));
+        }
+
+        if next <= freeblock {
+            break;
+        }
+
+        freeblock = next;
+    }
+
+    freeblocks
+}
+
+/// Scans the bytes for intact records, skipping a byte at a time where none decodes.
+fn scan_records(data: &[u8], header: &FileHeader) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let record = plausible_record_length(&data[pos..]).and_then(|length| {
+            decode_plausible(&data[pos..pos + length], header).map(|record| (record, length))
+        });
+
+        match record {
+            Some((record, length)) => {
+                records.push(record);
+                pos += length;
+            }
+            None => pos += 1,
+        }
+    }
+
+    records
+}
+
+/// Rebuilds the records of the cells making up the freeblock spanning `start..end`.
+///
+/// Adjacent freeblocks are merged into one, but the headers of the merged blocks are left in
+/// place, so a span that does not decode as a single cell is split at an inner freeblock
+/// header that reaches the end of the span.
+fn rebuild_freeblock_span(data: &[u8], start: usize, end: usize, header: &FileHeader) -> Option<Vec<Record>> {
+    if let Some(record) = rebuild_freeblock_record(&data[start + 4..end], header) {
+        return Some(vec![record]);
+    }
+
+    for split in start + 5..end.saturating_sub(4) {
+        if split + BigEndian::read_u16(&data[split + 2..]) as usize != end {
+            continue;
+        }
+
+        if let Some(last) = rebuild_freeblock_record(&data[split + 4..end], header) {
+            if let Some(mut records) = rebuild_freeblock_span(data, start, split, header) {
+                records.push(last);
+                return Some(records);
+            }
+        }
+    }
+
+    None
+}
+
+/// Rebuilds the record of a cell that was turned into a freeblock, given the bytes after
+/// the freeblock header.
+///
+/// The 4-byte freeblock header overwrites the start of the cell, that is the payload
+/// length, the rowid, the record header length and, for small rowids, the serial type of
+/// the first column. The remaining serial types are still intact, so this looks for a
+/// record header that, together with the body it describes, exactly fills the freeblock.
+/// Columns whose serial types were overwritten are assumed to be NULL, which holds for
+/// the usual `INTEGER PRIMARY KEY` column that aliases the rowid.
+fn rebuild_freeblock_record(data: &[u8], header: &FileHeader) -> Option<Record> {
+    for missing_columns in [1, 0, 2] {
+        let mut reader = Cursor::new(data);
+        let mut body_length = 0;
+
+        while let Ok(serial_type) = reader.read_var64() {
+            body_length += serial_type_length(u64::try_from(serial_type).ok()?)?;
+
+            let serial_types_length = reader.position() as usize;
+            let header_length = 1 + missing_columns + serial_types_length;
+
+            if header_length >= 0x80 || serial_types_length as u64 + body_length > data.len() as u64 {
+                break;
+            }
+
+            if serial_types_length as u64 + body_length == data.len() as u64 {
+                let mut buf = vec![header_length as u8];
+                buf.extend(std::iter::repeat_n(0, missing_columns));
+                buf.extend_from_slice(data);
+
+                if let Some(record) = decode_plausible(&buf, header) {
+                    return Some(record);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Decodes a record, rejecting those that are unlikely to be real data.
+fn decode_plausible(data: &[u8], header: &FileHeader) -> Option<Record> {
+    // Invalid text is a sign of bytes that are not a record, so it is never decoded lossily
+    let record = Record::read(&mut Cursor::new(data), header, &ReadOptions::default()).ok()?;
+
+    // Zero-filled space decodes as records of NULLs or NUL text, which real data rarely contains
+    let all_null = record.entries.iter().all(RecordEntry::is_null);
+    let has_nul_text = record.entries.iter().any(|entry| matches!(entry, RecordEntry::Text(text) if text.contains('\0')));
+
+    if all_null || has_nul_text { None } else { Some(record) }
+}
+
+/// Checks whether `data` starts with a well-formed record header whose body fits into it,
+/// and returns the total length of the record if so.
+pub(crate) fn plausible_record_length(data: &[u8]) -> Option<usize> {
+    let mut reader = Cursor::new(data);
+    let header_length = reader.read_var64().ok()?;
+
+    if header_length < 2 || header_length as u64 > data.len() as u64 {
+        return None;
+    }
+
+    let mut body_length = 0u64;
+
+    while reader.position() < header_length as u64 {
+        body_length = body_length.checked_add(serial_type_length(u64::try_from(reader.read_var64().ok()?).ok()?)?)?;
+    }
+
+    let length = (header_length as u64).checked_add(body_length)?;
+
+    if reader.position() != header_length as u64 || length > data.len() as u64 {
+        return None;
+    }
+
+    Some(length as usize)
+}
+
@@ -0,0 +1,208 @@
+use std::io::{Error, ErrorKind, Read, Seek};
+
+use crate::{Database, Record, RecordEntry, TableDef};
+use crate::FilePageType::{TableInterior, TableLeaf};
+use crate::sql::{index_column_list, join_tokens, tokenize, Token};
+
+/// Root page of the `sqlite_master` table, which is always the first page of the database.
+pub const SCHEMA_ROOT_PAGE: u32 = 1;
+
+/// Definition of the `sqlite_master` table, which is not itself listed in the schema.
+const SCHEMA_TABLE_SQL: &str = "CREATE TABLE sqlite_master(type text, name text, tbl_name text, rootpage integer, sql text)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SchemaEntryType {
+    Table,
+    Index,
+    View,
+    Trigger,
+}
+
+/// A single row of the `sqlite_master` table.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SchemaEntry {
+    pub typ: SchemaEntryType,
+    pub name: String,
+    pub table_name: String,
+    /// Root page of the entry's b-tree, or `None` for views and triggers.
+    pub root_page: Option<u32>,
+    /// SQL text of the entry, or `None` for automatically created indexes.
+    pub sql: Option<String>,
+}
+
+impl SchemaEntry {
+    fn from_record(rowid: i64, record: &Record) -> std::io::Result<Self> {
+        let malformed = |what: &str| Error::new(ErrorKind::InvalidData, format!("Malformed sqlite_master row {}: {}", rowid, what));
+
+        let [typ, name, table_name, root_page, sql] = record.entries.as_slice() else {
+            return Err(malformed(&format!("expected 5 columns, got {}", record.entries.len())));
+        };
+
+        let typ = match typ {
+            RecordEntry::Text(typ) if typ == "table" => SchemaEntryType::Table,
+            RecordEntry::Text(typ) if typ == "index" => SchemaEntryType::Index,
+            RecordEntry::Text(typ) if typ == "view" => SchemaEntryType::View,
+            RecordEntry::Text(typ) if typ == "trigger" => SchemaEntryType::Trigger,
+            x => return Err(malformed(&format!("unknown type {:?}", x))),
+        };
+
+        let name = match name {
+            RecordEntry::Text(name) => name.clone(),
+            x => return Err(malformed(&format!("invalid name {:?}", x))),
+        };
+
+        let table_name = match table_name {
+            RecordEntry::Text(table_name) => table_name.clone(),
+            x => return Err(malformed(&format!("invalid tbl_name {:?}", x))),
+        };
+
+        let root_page = match root_page {
+            RecordEntry::Null | RecordEntry::Integer(0) => None,
+            RecordEntry::Integer(root_page) => Some(u32::try_from(*root_page).map_err(|_| malformed(&format!("invalid rootpage {}", root_page)))?),
+            x => return Err(malformed(&format!("invalid rootpage {:?}", x))),
+        };
+
+        let sql = match sql {
+            RecordEntry::Null => None,
+            RecordEntry::Text(sql) => Some(sql.clone()),
+            x => return Err(malformed(&format!("invalid sql {:?}", x))),
+        };
+
+        Ok(SchemaEntry {
+            typ,
+            name,
+            table_name,
+            root_page,
+            sql,
+        })
+    }
+
+    /// Whether this entry was created by SQLite itself, such as `sqlite_sequence`, `sqlite_stat1`
+    /// or the `sqlite_autoindex_*` indexes. Names starting with `sqlite_` are reserved for them.
+    pub fn is_internal(&self) -> bool {
+        self.name.get(..7).is_some_and(|prefix| prefix.eq_ignore_ascii_case("sqlite_"))
+    }
+
+    /// Parses the `CREATE TABLE` statement of this entry.
+    pub fn table_def(&self) -> std::io::Result<TableDef> {
+        match (&self.typ, &self.sql) {
+            (SchemaEntryType::Table, Some(sql)) => TableDef::parse(sql),
+            _ => Err(Error::new(ErrorKind::InvalidInput, format!("Schema entry {} is not a table", self.name))),
+        }
+    }
+}
+
+/// A table of the schema, see [`Database::tables`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TableInfo {
+    pub name: String,
+    /// Root page of the table's b-tree, or `None` for virtual tables.
+    pub root_page: Option<u32>,
+}
+
+/// An index of the schema, see [`Database::indexes`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IndexInfo {
+    pub name: String,
+    /// Name of the table the index belongs to.
+    pub table_name: String,
+    pub root_page: Option<u32>,
+    /// Whether the index was created by SQLite for a `UNIQUE` or `PRIMARY KEY` constraint,
+    /// in which case it has no SQL of its own.
+    pub automatic: bool,
+    /// Indexed columns, in key order, from the `CREATE INDEX` statement, with the `COLLATE`,
+    /// `ASC` and `DESC` clauses left out. An indexed expression is given as its SQL text.
+    /// Empty for automatic indexes.
+    pub columns: Vec<String>,
+}
+
+impl IndexInfo {
+    pub(crate) fn from_entry(entry: SchemaEntry) -> std::io::Result<Self> {
+        let columns = match &entry.sql {
+            Some(sql) => {
+                let tokens = tokenize(sql)?;
+                index_column_list(&tokens)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Expected column list in CREATE INDEX statement: {}", sql)))?
+                    .into_iter()
+                    .map(|column| {
+                        let end = column.iter()
+                            .position(|token| ["COLLATE", "ASC", "DESC"].iter().any(|keyword| token.is_keyword(keyword)))
+                            .unwrap_or(column.len());
+                        match &column[..end] {
+                            [Token::Word(name) | Token::String(name)] => name.clone(),
+                            expression => join_tokens(expression),
+                        }
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Ok(IndexInfo {
+            automatic: entry.sql.is_none(),
+            name: entry.name,
+            table_name: entry.table_name,
+            root_page: entry.root_page,
+            columns,
+        })
+    }
+}
+
+/// The catalog of tables, indexes, views and triggers stored in `sqlite_master`.
+#[derive(Debug)]
+pub struct Schema {
+    pub entries: Vec<SchemaEntry>,
+}
+
+impl Schema {
+    pub fn read<R>(database: &mut Database<R>) -> std::io::Result<Self>
+        where R: Read + Seek
+    {
+        let root = database.page(SCHEMA_ROOT_PAGE)?;
+        if !matches!(root.header.typ, TableInterior | TableLeaf) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has type {:?}, but the schema must be a table b-tree", SCHEMA_ROOT_PAGE, root.header.typ)));
+        }
+
+        let entries = database.rows(SCHEMA_ROOT_PAGE)
+            .map(|row| row.and_then(|(rowid, record)| SchemaEntry::from_record(rowid, &record)))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        Ok(Schema {
+            entries
+        })
+    }
+
+    /// Definition of the `sqlite_master` table rooted at [`SCHEMA_ROOT_PAGE`].
+    pub fn master_table_def() -> TableDef {
+        TableDef::parse(SCHEMA_TABLE_SQL).unwrap()
+    }
+
+    /// Finds the table or index whose b-tree is rooted at the given page.
+    pub fn entry_by_root_page(&self, root_page: u32) -> Option<&SchemaEntry> {
+        self.entries.iter().find(|entry| entry.root_page == Some(root_page))
+    }
+
+    /// Finds an entry by its name, ignoring ASCII case as SQLite does for identifiers.
+    pub fn entry(&self, name: &str) -> Option<&SchemaEntry> {
+        self.entries.iter().find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Iterates over the tables stored in a b-tree, in schema order, leaving out virtual tables.
+    /// Internal tables, see [`SchemaEntry::is_internal`], are left out unless `include_internal` is set.
+    pub fn tables(&self, include_internal: bool) -> impl Iterator<Item = &SchemaEntry> {
+        self.entries.iter()
+            .filter(move |entry| entry.typ == SchemaEntryType::Table && entry.root_page.is_some())
+            .filter(move |entry| include_internal || !entry.is_internal())
+    }
+
+    /// Root page of the table with the given name, ignoring ASCII case.
+    pub fn table_root_page(&self, name: &str) -> Option<u32> {
+        self.entries.iter()
+            .find(|entry| entry.typ == SchemaEntryType::Table && entry.name.eq_ignore_ascii_case(name))
+            .and_then(|entry| entry.root_page)
+    }
+}
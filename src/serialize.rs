@@ -0,0 +1,44 @@
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::{Record, RecordEntry, RecordView};
+
+/// Serializes NULL as a unit, blobs as bytes and everything else as the matching primitive.
+impl Serialize for RecordEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        match self {
+            RecordEntry::Null => serializer.serialize_none(),
+            RecordEntry::Integer(value) => serializer.serialize_i64(*value),
+            RecordEntry::Float(value) => serializer.serialize_f64(*value),
+            RecordEntry::Blob(value) => serializer.serialize_bytes(value),
+            RecordEntry::Text(value) => serializer.serialize_str(value),
+        }
+    }
+}
+
+/// Serializes the record as a sequence of its entries.
+impl Serialize for Record {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut seq = serializer.serialize_seq(Some(self.entries.len()))?;
+        for entry in self.entries.iter() {
+            seq.serialize_element(entry)?;
+        }
+        seq.end()
+    }
+}
+
+/// Serializes the record as a map keyed by column names.
+impl Serialize for RecordView<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, entry) in self.iter() {
+            map.serialize_entry(name, &entry)?;
+        }
+        map.end()
+    }
+}
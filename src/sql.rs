@@ -0,0 +1,197 @@
+use std::io::{Error, ErrorKind};
+
+/// A token of the SQL statements stored in `sqlite_master`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    /// A keyword or an identifier, with any quoting removed.
+    Word(String),
+    /// A string literal, with quotes removed and escaped quotes unescaped.
+    String(String),
+    /// A numeric literal, as written.
+    Number(String),
+    /// A blob literal such as `x'00ff'`, as written.
+    Blob(String),
+    /// Any other single character, such as `(`, `)` or `,`.
+    Punct(char),
+}
+
+impl Token {
+    /// Checks whether this token is the given keyword, ignoring case.
+    pub(crate) fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Renders the token back into SQL text.
+    pub(crate) fn to_sql(&self) -> String {
+        match self {
+            Token::Word(word) => word.clone(),
+            Token::String(string) => format!("'{}'", string.replace('\'', "''")),
+            Token::Number(number) | Token::Blob(number) => number.clone(),
+            Token::Punct(punct) => punct.to_string(),
+        }
+    }
+}
+
+/// Splits a SQL statement into tokens, skipping whitespace and comments.
+pub(crate) fn tokenize(sql: &str) -> std::io::Result<Vec<Token>> {
+    let chars = sql.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+
+        if c.is_whitespace() {
+            pos += 1;
+        } else if c == '-' && chars.get(pos + 1) == Some(&'-') {
+            while pos < chars.len() && chars[pos] != '\n' {
+                pos += 1;
+            }
+        } else if c == '/' && chars.get(pos + 1) == Some(&'*') {
+            pos += 2;
+            while pos < chars.len() && !(chars[pos] == '*' && chars.get(pos + 1) == Some(&'/')) {
+                pos += 1;
+            }
+            pos += 2;
+        } else if (c == 'x' || c == 'X') && chars.get(pos + 1) == Some(&'\'') {
+            let (_, end) = read_quoted(&chars, pos + 1, '\'')?;
+            tokens.push(Token::Blob(chars[pos..end].iter().collect()));
+            pos = end;
+        } else if c == '\'' {
+            let (string, end) = read_quoted(&chars, pos, '\'')?;
+            tokens.push(Token::String(string));
+            pos = end;
+        } else if c == '"' || c == '`' {
+            let (word, end) = read_quoted(&chars, pos, c)?;
+            tokens.push(Token::Word(word));
+            pos = end;
+        } else if c == '[' {
+            let end = chars[pos..].iter().position(|&c| c == ']')
+                .map(|offset| pos + offset)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Unterminated [identifier] in SQL"))?;
+            tokens.push(Token::Word(chars[pos + 1..end].iter().collect()));
+            pos = end + 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(pos + 1).is_some_and(|c| c.is_ascii_digit())) {
+            let start = pos;
+            while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '.'
+                || ((chars[pos] == '+' || chars[pos] == '-') && matches!(chars[pos - 1], 'e' | 'E'))) {
+                pos += 1;
+            }
+            tokens.push(Token::Number(chars[start..pos].iter().collect()));
+        } else if c.is_alphanumeric() || c == '_' || c == '$' {
+            let start = pos;
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_' || chars[pos] == '$') {
+                pos += 1;
+            }
+            tokens.push(Token::Word(chars[start..pos].iter().collect()));
+        } else {
+            tokens.push(Token::Punct(c));
+            pos += 1;
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a quoted string starting at `start`, where a doubled quote character stands for
+/// itself. Returns the unquoted contents and the position after the closing quote.
+fn read_quoted(chars: &[char], start: usize, quote: char) -> std::io::Result<(String, usize)> {
+    let mut result = String::new();
+    let mut pos = start + 1;
+
+    loop {
+        match chars.get(pos) {
+            Some(&c) if c == quote && chars.get(pos + 1) == Some(&quote) => {
+                result.push(quote);
+                pos += 2;
+            }
+            Some(&c) if c == quote => return Ok((result, pos + 1)),
+            Some(&c) => {
+                result.push(c);
+                pos += 1;
+            }
+            None => return Err(Error::new(ErrorKind::InvalidData, format!("Unterminated {}quoted{} text in SQL", quote, quote))),
+        }
+    }
+}
+
+/// Advances past the token at `pos` if it is the given keyword.
+pub(crate) fn accept_keyword(tokens: &[Token], pos: &mut usize, keyword: &str) -> bool {
+    match tokens.get(*pos) {
+        Some(token) if token.is_keyword(keyword) => {
+            *pos += 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Splits tokens on commas that are not nested inside parentheses.
+pub(crate) fn split_top_level(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Punct('(') => depth += 1,
+            Token::Punct(')') => depth -= 1,
+            Token::Punct(',') if depth == 0 => {
+                parts.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&tokens[start..]);
+    parts
+}
+
+/// Splits the column list of a `CREATE INDEX` statement into the tokens of each column,
+/// which may be an expression followed by `COLLATE`, `ASC` or `DESC`.
+pub(crate) fn index_column_list(tokens: &[Token]) -> Option<Vec<&[Token]>> {
+    let open = tokens.iter().position(|token| *token == Token::Punct('('))?;
+    let close = matching_paren(tokens, open)?;
+    Some(split_top_level(&tokens[open + 1..close]))
+}
+
+/// Finds the index of the parenthesis closing the one at `open`.
+pub(crate) fn matching_paren(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Punct('(') => depth += 1,
+            Token::Punct(')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Joins tokens back into SQL text, separating words with spaces. The sign of a number that
+/// starts an expression, as in `(-1)`, is kept next to it.
+pub(crate) fn join_tokens(tokens: &[Token]) -> String {
+    let mut result = String::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        let signed = matches!(token, Token::Number(_))
+            && index > 0
+            && matches!(tokens[index - 1], Token::Punct('+' | '-'))
+            && (index == 1 || matches!(tokens[index - 2], Token::Punct('(' | ',')));
+        let glue = matches!(token, Token::Punct('(' | ')' | ',')) || result.ends_with('(') || signed;
+        if !result.is_empty() && !glue {
+            result.push(' ');
+        }
+        result.push_str(&token.to_sql());
+    }
+
+    result
+}
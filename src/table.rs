@@ -0,0 +1,136 @@
+use std::io::{Error, ErrorKind, Read, Seek};
+
+use crate::{Database, IndexIterator, Record, RecordView, TableDef, TableIterator};
+use crate::FilePageType::{IndexInterior, IndexLeaf, TableInterior, TableLeaf};
+
+/// A row of a [`Table`], with its columns in declared order.
+#[derive(Debug, Clone)]
+pub struct Row {
+    /// Rowid of the row, or `None` for `WITHOUT ROWID` tables.
+    pub rowid: Option<i64>,
+    pub record: Record,
+}
+
+/// A table of a database, found by name through [`Database::table`].
+pub struct Table<'a, R> {
+    database: &'a mut Database<R>,
+    pub root_page: u32,
+    pub def: TableDef,
+}
+
+impl<'a, R> Table<'a, R>
+    where R: Read + Seek
+{
+    pub(crate) fn new(database: &'a mut Database<R>, root_page: u32, def: TableDef) -> Self {
+        Table {
+            database,
+            root_page,
+            def,
+        }
+    }
+
+    /// Iterates over the rows of the table, in rowid order or, for `WITHOUT ROWID` tables,
+    /// in primary key order. The `INTEGER PRIMARY KEY` column, if any, holds the rowid.
+    pub fn rows(&mut self) -> TableRows<'_, R> {
+        let inner = if self.def.without_rowid {
+            TableRowsInner::Index(self.database.index_entries(self.root_page))
+        } else {
+            TableRowsInner::Table(self.database.rows(self.root_page))
+        };
+
+        TableRows {
+            def: &self.def,
+            inner,
+        }
+    }
+
+    /// Looks up the rows with the given rowids, see [`Database::find_rows`]. Rowids without
+    /// a row are skipped, and the rows are returned in rowid order, each only once.
+    pub fn rows_by_rowids(&mut self, rowids: &[i64]) -> std::io::Result<Vec<(i64, Record)>> {
+        if self.def.without_rowid {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Table {} is a WITHOUT ROWID table and has no rowids", self.def.name)));
+        }
+
+        let rows = self.database.find_rows(self.root_page, rowids)?;
+
+        Ok(rows.into_iter()
+            .map(|(rowid, record)| (rowid, Row::from_stored(&self.def, Some(rowid), record).record))
+            .collect())
+    }
+
+    /// Counts the rows of the table from the number of cells on each page, without reading
+    /// the cells themselves beyond the child pointers of interior pages.
+    pub fn count_rows(&mut self) -> std::io::Result<u64> {
+        let pager = self.database.pager();
+        let database_size = pager.header().database_size();
+        let mut pages = vec![self.root_page];
+        let mut pages_visited = 0;
+        let mut count = 0;
+
+        while let Some(page_number) = pages.pop() {
+            // A b-tree cannot have more pages than the database, unless its pages form a cycle
+            pages_visited += 1;
+            if pages_visited > database_size {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Table b-tree rooted at page {} has more pages than the database", self.root_page)));
+            }
+
+            let pointers = pager.cell_pointers(page_number)?;
+
+            match (&pointers.header.typ, self.def.without_rowid) {
+                (TableLeaf, false) | (IndexLeaf | IndexInterior, true) => count += pointers.header.cells_count as u64,
+                (TableInterior, false) => {}
+                (x, _) => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for the b-tree of table {}", page_number, x, self.def.name))),
+            }
+
+            pages.extend(pager.child_pages(&pointers)?);
+        }
+
+        Ok(count)
+    }
+}
+
+impl Row {
+    /// Makes a row of the given table from a record as stored in its b-tree and the rowid of
+    /// its cell, rearranging the record into declared order with [`TableDef::declared_order`]
+    /// and filling the `INTEGER PRIMARY KEY` column, if any, with the rowid.
+    pub fn from_stored(table: &TableDef, rowid: Option<i64>, record: Record) -> Row {
+        let mut record = table.declared_order(record);
+        if let Some(rowid) = rowid {
+            table.set_rowid_alias(&mut record, rowid);
+        }
+
+        Row {
+            rowid,
+            record,
+        }
+    }
+
+    /// Pairs the entries of this row with the columns of the table it came from.
+    pub fn with_columns<'a>(&'a self, table: &'a TableDef) -> RecordView<'a> {
+        self.record.with_columns(table)
+    }
+}
+
+enum TableRowsInner<'a, R> {
+    Table(TableIterator<'a, R>),
+    Index(IndexIterator<'a, R>),
+}
+
+/// Iterates over the rows of a [`Table`].
+pub struct TableRows<'a, R> {
+    def: &'a TableDef,
+    inner: TableRowsInner<'a, R>,
+}
+
+impl<R> Iterator for TableRows<'_, R>
+    where R: Read + Seek
+{
+    type Item = std::io::Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match &mut self.inner {
+            TableRowsInner::Table(rows) => rows.next()?.map(|(rowid, record)| Row::from_stored(self.def, Some(rowid), record)),
+            TableRowsInner::Index(entries) => entries.next()?.map(|record| Row::from_stored(self.def, None, record)),
+        })
+    }
+}
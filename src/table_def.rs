@@ -0,0 +1,572 @@
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind};
+
+use crate::{Collation, Record, RecordEntry};
+use crate::sql::{accept_keyword, index_column_list, join_tokens, matching_paren, split_top_level, tokenize, Token};
+
+/// Keywords that end a column's type name and start its constraints.
+const COLUMN_CONSTRAINT_KEYWORDS: [&str; 11] = [
+    "CONSTRAINT", "PRIMARY", "NOT", "NULL", "UNIQUE", "CHECK", "DEFAULT", "COLLATE", "REFERENCES", "GENERATED", "AS",
+];
+
+/// Keywords that start a table constraint rather than a column definition.
+const TABLE_CONSTRAINT_KEYWORDS: [&str; 5] = ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"];
+
+/// Type affinity of a column, derived from its declared type.
+/// See [Determination Of Column Affinity](https://www.sqlite.org/datatype3.html#determination_of_column_affinity).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    Text,
+    Numeric,
+    Integer,
+    Real,
+    Blob,
+}
+
+impl Affinity {
+    pub fn from_declared_type(declared_type: Option<&str>) -> Self {
+        let declared_type = match declared_type {
+            Some(declared_type) => declared_type.to_ascii_uppercase(),
+            None => return Affinity::Blob,
+        };
+
+        if declared_type.contains("INT") {
+            Affinity::Integer
+        } else if ["CHAR", "CLOB", "TEXT"].iter().any(|x| declared_type.contains(x)) {
+            Affinity::Text
+        } else if declared_type.contains("BLOB") {
+            Affinity::Blob
+        } else if ["REAL", "FLOA", "DOUB"].iter().any(|x| declared_type.contains(x)) {
+            Affinity::Real
+        } else {
+            Affinity::Numeric
+        }
+    }
+
+    /// Converts a value the way SQLite does for a column of this affinity, before comparing
+    /// the column with it: numeric text becomes a number for the numeric affinities, and numbers
+    /// become text for the `TEXT` affinity. Other values are left as they are.
+    ///
+    /// SQLite already applies the affinity when storing values, so this only changes the values
+    /// compared with a column, and the values of columns written by other tools.
+    pub fn apply<'a>(&self, entry: &'a RecordEntry) -> Cow<'a, RecordEntry> {
+        match (self, entry) {
+            (Affinity::Integer | Affinity::Numeric, RecordEntry::Text(text)) => parse_numeric(text).map_or(Cow::Borrowed(entry), Cow::Owned),
+            (Affinity::Real, RecordEntry::Text(text)) => match parse_numeric(text) {
+                Some(RecordEntry::Integer(value)) => Cow::Owned(RecordEntry::Float(value as f64)),
+                Some(number) => Cow::Owned(number),
+                None => Cow::Borrowed(entry),
+            },
+            (Affinity::Text, RecordEntry::Integer(_) | RecordEntry::Float(_)) => Cow::Owned(RecordEntry::Text(entry.to_string())),
+            _ => Cow::Borrowed(entry),
+        }
+    }
+}
+
+/// Parses text that SQLite considers a number, such as ` 42` or `-1.5e3`. Floats without a
+/// fractional part become integers, as they do in columns with the `NUMERIC` affinity.
+fn parse_numeric(text: &str) -> Option<RecordEntry> {
+    let text = text.trim();
+
+    // Rust also parses words like "inf" and "NaN", which SQLite keeps as text
+    if !text.chars().all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')) {
+        return None;
+    }
+
+    if let Ok(value) = text.parse::<i64>() {
+        return Some(RecordEntry::Integer(value));
+    }
+
+    let value = text.parse::<f64>().ok()?;
+    if value.fract() == 0.0 && value.abs() < 9.2e18 {
+        Some(RecordEntry::Integer(value as i64))
+    } else {
+        Some(RecordEntry::Float(value))
+    }
+}
+
+/// How the value of a generated column is kept, see [`ColumnDef::generated`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generated {
+    /// Computed when the row is written, and stored in the record like any other column.
+    Stored,
+    /// Computed when the row is read, and left out of the record.
+    Virtual,
+}
+
+#[derive(Debug, Clone)]
+pub struct ColumnDef {
+    pub name: String,
+    pub declared_type: Option<String>,
+    pub affinity: Affinity,
+    /// Whether the column is computed from an `AS (...)` clause, optionally preceded by
+    /// `GENERATED ALWAYS`, or `None` for ordinary columns.
+    pub generated: Option<Generated>,
+    /// Collation of the column from its `COLLATE` clause. Collations defined by applications
+    /// are not known, so they compare like `BINARY`, as does a column without the clause.
+    pub collation: Collation,
+    /// Whether the column is declared `NOT NULL`.
+    pub not_null: bool,
+    /// SQL text of the expression of the `DEFAULT` clause, such as `0`, `'none'` or
+    /// `CURRENT_TIMESTAMP`, or `None` if the column has no default. See [`ColumnDef::default_value`].
+    pub default: Option<String>,
+}
+
+impl ColumnDef {
+    fn parse(tokens: &[Token]) -> std::io::Result<Self> {
+        let name = match tokens.first() {
+            Some(Token::Word(name) | Token::String(name)) => name.clone(),
+            x => return Err(Error::new(ErrorKind::InvalidData, format!("Expected column name, got {:?}", x))),
+        };
+
+        let type_end = tokens.iter()
+            .skip(1)
+            .position(|token| COLUMN_CONSTRAINT_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword)))
+            .map_or(tokens.len(), |offset| offset + 1);
+
+        let declared_type = match join_tokens(&tokens[1..type_end]) {
+            declared_type if declared_type.is_empty() => None,
+            declared_type => Some(declared_type),
+        };
+
+        // Generated columns are VIRTUAL unless declared STORED. An AS inside parentheses belongs
+        // to an expression, such as a CAST in a DEFAULT or CHECK clause
+        let mut generated = None;
+        let mut depth = 0;
+        for (index, token) in tokens.iter().enumerate().skip(1) {
+            match token {
+                Token::Punct('(') => depth += 1,
+                Token::Punct(')') => depth -= 1,
+                _ if depth == 0 && token.is_keyword("AS") && tokens.get(index + 1) == Some(&Token::Punct('(')) => {
+                    let close = matching_paren(tokens, index + 1)
+                        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Expected closing parenthesis in the AS clause of column {}", name)))?;
+                    generated = Some(match tokens.get(close + 1) {
+                        Some(token) if token.is_keyword("STORED") => Generated::Stored,
+                        _ => Generated::Virtual,
+                    });
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ColumnDef {
+            affinity: Affinity::from_declared_type(declared_type.as_deref()),
+            name,
+            declared_type,
+            generated,
+            collation: parse_collation(&tokens[1..]),
+            not_null: top_level(&tokens[1..]).any(|index| tokens[1 + index].is_keyword("NOT") && tokens.get(2 + index).is_some_and(|token| token.is_keyword("NULL"))),
+            default: top_level(&tokens[1..])
+                .find(|&index| tokens[1 + index].is_keyword("DEFAULT"))
+                .and_then(|index| parse_default(&tokens[2 + index..])),
+        })
+    }
+
+    /// Evaluates the `DEFAULT` clause of the column, if it is a constant such as a number,
+    /// a string, a blob, `NULL`, `TRUE` or `FALSE`, optionally in parentheses, and converts it
+    /// to the affinity of the column as SQLite does. Other expressions, such as `CURRENT_TIME`,
+    /// are only evaluated when a row is written, and give `None`, as does a column without default.
+    ///
+    /// SQLite reads the columns missing from records written before an `ALTER TABLE ADD COLUMN`
+    /// as their default, which can only be a constant, see [`TableDef::declared_order`].
+    pub fn default_value(&self) -> Option<RecordEntry> {
+        let tokens = tokenize(self.default.as_deref()?).ok()?;
+        let mut tokens = tokens.as_slice();
+
+        while let [Token::Punct('('), inner @ .., Token::Punct(')')] = tokens {
+            tokens = inner;
+        }
+
+        let number = |sign: &str, number: &str| match number.strip_prefix("0x").or_else(|| number.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16).ok().map(|value| RecordEntry::Integer(if sign == "-" { (value as i64).wrapping_neg() } else { value as i64 })),
+            None => parse_numeric(&format!("{}{}", sign, number)).map(|value| match (value, number.contains(['.', 'e', 'E'])) {
+                // A literal with a decimal point or an exponent is a float, even if it is integral
+                (RecordEntry::Integer(value), true) => RecordEntry::Float(value as f64),
+                (value, _) => value,
+            }),
+        };
+
+        let value = match tokens {
+            [Token::Number(value)] => number("", value)?,
+            [Token::Punct(sign @ ('+' | '-')), Token::Number(value)] => number(&sign.to_string(), value)?,
+            [Token::String(text)] => RecordEntry::Text(text.clone()),
+            [Token::Blob(blob)] => RecordEntry::Blob(parse_hex(&blob[2..blob.len() - 1])?),
+            [token] if token.is_keyword("NULL") => RecordEntry::Null,
+            [token] if token.is_keyword("TRUE") => RecordEntry::Integer(1),
+            [token] if token.is_keyword("FALSE") => RecordEntry::Integer(0),
+            _ => return None,
+        };
+
+        // Unlike when comparing, numbers are stored as the column requires: REAL columns turn
+        // integers into floats, and the other numeric columns turn integral floats into integers.
+        // Numbers in columns without affinity are stored like in NUMERIC ones
+        match (self.affinity, self.affinity.apply(&value).into_owned()) {
+            (Affinity::Real, RecordEntry::Integer(value)) => Some(RecordEntry::Float(value as f64)),
+            (Affinity::Integer | Affinity::Numeric | Affinity::Blob, RecordEntry::Float(value)) if value.fract() == 0.0 && value.abs() < 9.2e18 => Some(RecordEntry::Integer(value as i64)),
+            (_, value) => Some(value),
+        }
+    }
+}
+
+/// Indices of the tokens that are not nested inside parentheses.
+fn top_level(tokens: &[Token]) -> impl Iterator<Item = usize> + '_ {
+    let mut depth = 0;
+
+    tokens.iter().enumerate().filter_map(move |(index, token)| {
+        match token {
+            Token::Punct('(') => depth += 1,
+            Token::Punct(')') => depth -= 1,
+            _ => {}
+        }
+        (depth == 0 && *token != Token::Punct(')')).then_some(index)
+    })
+}
+
+/// Renders the expression of a `DEFAULT` clause, which is a literal, a signed number,
+/// a word such as `NULL` or `CURRENT_TIME`, or an expression in parentheses.
+fn parse_default(tokens: &[Token]) -> Option<String> {
+    match tokens {
+        [Token::Punct('('), ..] => matching_paren(tokens, 0).map(|close| join_tokens(&tokens[..=close])),
+        [Token::Punct(sign @ ('+' | '-')), Token::Number(number), ..] => Some(format!("{}{}", sign, number)),
+        [token, ..] => Some(token.to_sql()),
+        [] => None,
+    }
+}
+
+/// Decodes the hex digits of a blob literal.
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Finds the collation named by a `COLLATE` clause that is not nested inside parentheses,
+/// such as one in a `CHECK` constraint, defaulting to `BINARY`.
+fn parse_collation(tokens: &[Token]) -> Collation {
+    let mut depth = 0;
+
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Punct('(') => depth += 1,
+            Token::Punct(')') => depth -= 1,
+            _ if depth == 0 && token.is_keyword("COLLATE") => return match tokens.get(index + 1) {
+                Some(Token::Word(name) | Token::String(name)) => Collation::from_name(name).unwrap_or_default(),
+                _ => Collation::Binary,
+            },
+            _ => {}
+        }
+    }
+
+    Collation::Binary
+}
+
+/// Table definition parsed from the `CREATE TABLE` statement stored in `sqlite_master`.
+#[derive(Debug, Clone)]
+pub struct TableDef {
+    pub name: String,
+    pub columns: Vec<ColumnDef>,
+    /// Indices of the primary key columns, in key order. Empty if the table declares no
+    /// primary key. The value of an `INTEGER PRIMARY KEY` column is the rowid of the row,
+    /// see [`TableDef::rowid_alias`] and [`TableDef::primary_key_values`].
+    pub primary_key: Vec<usize>,
+    /// Whether the table is declared `WITHOUT ROWID`, and thus stored in an index b-tree
+    /// keyed by its primary key.
+    pub without_rowid: bool,
+}
+
+impl TableDef {
+    pub fn parse(sql: &str) -> std::io::Result<Self> {
+        let tokens = tokenize(sql)?;
+        let unexpected = |what: &str| Error::new(ErrorKind::InvalidData, format!("Expected {} in CREATE TABLE statement: {}", what, sql));
+
+        let mut pos = 0;
+
+        if !accept_keyword(&tokens, &mut pos, "CREATE") {
+            return Err(unexpected("CREATE"));
+        }
+        if !accept_keyword(&tokens, &mut pos, "TEMP") {
+            accept_keyword(&tokens, &mut pos, "TEMPORARY");
+        }
+        if !accept_keyword(&tokens, &mut pos, "TABLE") {
+            return Err(unexpected("TABLE"));
+        }
+        if accept_keyword(&tokens, &mut pos, "IF") && !(accept_keyword(&tokens, &mut pos, "NOT") && accept_keyword(&tokens, &mut pos, "EXISTS")) {
+            return Err(unexpected("IF NOT EXISTS"));
+        }
+
+        let mut name = match tokens.get(pos) {
+            Some(Token::Word(name) | Token::String(name)) => name.clone(),
+            _ => return Err(unexpected("table name")),
+        };
+        pos += 1;
+
+        if tokens.get(pos) == Some(&Token::Punct('.')) {
+            name = match tokens.get(pos + 1) {
+                Some(Token::Word(name) | Token::String(name)) => name.clone(),
+                _ => return Err(unexpected("table name")),
+            };
+            pos += 2;
+        }
+
+        if tokens.get(pos) != Some(&Token::Punct('(')) {
+            return Err(unexpected("column definitions"));
+        }
+
+        let end = matching_paren(&tokens, pos).ok_or_else(|| unexpected("closing parenthesis"))?;
+        let mut columns = Vec::new();
+        let mut primary_key_names = Vec::new();
+
+        for definition in split_top_level(&tokens[pos + 1..end]) {
+            let is_table_constraint = definition.first()
+                .is_some_and(|token| TABLE_CONSTRAINT_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword)));
+
+            let primary_key = definition.windows(2).position(|pair| pair[0].is_keyword("PRIMARY") && pair[1].is_keyword("KEY"));
+
+            if !is_table_constraint {
+                let column = ColumnDef::parse(definition)?;
+                if primary_key.is_some() {
+                    primary_key_names.push(column.name.clone());
+                }
+                columns.push(column);
+            } else if let Some(primary_key) = primary_key {
+                // The key columns are listed in parentheses, each optionally followed by COLLATE, ASC or DESC
+                let open = primary_key + 2;
+                let close = matching_paren(definition, open).ok_or_else(|| unexpected("PRIMARY KEY columns"))?;
+
+                for key_column in split_top_level(&definition[open + 1..close]) {
+                    match key_column.first() {
+                        Some(Token::Word(name) | Token::String(name)) => primary_key_names.push(name.clone()),
+                        _ => return Err(unexpected("PRIMARY KEY column name")),
+                    }
+                }
+            }
+        }
+
+        // Table options such as WITHOUT ROWID and STRICT follow the column definitions
+        let without_rowid = split_top_level(&tokens[end + 1..]).iter()
+            .any(|option| matches!(option, [without, rowid] if without.is_keyword("WITHOUT") && rowid.is_keyword("ROWID")));
+
+        let mut table = TableDef {
+            name,
+            columns,
+            primary_key: Vec::new(),
+            without_rowid,
+        };
+
+        table.primary_key = primary_key_names.iter()
+            .map(|name| table.column_index(name).ok_or_else(|| unexpected(&format!("PRIMARY KEY column {}", name))))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        Ok(table)
+    }
+
+    /// Finds the index of a column by name, ignoring case as SQLite does.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Index of the `INTEGER PRIMARY KEY` column, which is an alias for the rowid and stored
+    /// as NULL in the record.
+    pub fn rowid_alias(&self) -> Option<usize> {
+        match self.primary_key.as_slice() {
+            [index] if !self.without_rowid => self.columns[*index].declared_type.as_deref()
+                .filter(|declared_type| declared_type.eq_ignore_ascii_case("INTEGER"))
+                .map(|_| *index),
+            _ => None,
+        }
+    }
+
+    /// Fills the `INTEGER PRIMARY KEY` column of a record, which is stored as NULL, with its rowid.
+    pub fn set_rowid_alias(&self, record: &mut Record, rowid: i64) {
+        if let Some(entry) = self.rowid_alias().and_then(|index| record.entries.get_mut(index)) {
+            *entry = RecordEntry::Integer(rowid);
+        }
+    }
+
+    /// Returns the primary key of a row, in key order, from a record in declared column order
+    /// and the rowid of its cell. The `INTEGER PRIMARY KEY` column is stored as NULL in the
+    /// record, so its value is taken from the rowid, if given.
+    pub fn primary_key_values(&self, rowid: Option<i64>, record: &Record) -> Vec<RecordEntry> {
+        let rowid_alias = self.rowid_alias();
+
+        self.primary_key.iter()
+            .map(|&index| match rowid {
+                Some(rowid) if Some(index) == rowid_alias => RecordEntry::Integer(rowid),
+                _ => record.entries.get(index).cloned().unwrap_or(RecordEntry::Null),
+            })
+            .collect()
+    }
+
+    /// Returns the collation of each column of an index on this table, from the `CREATE INDEX`
+    /// statement stored in `sqlite_master`. An index column without a `COLLATE` clause uses
+    /// the collation of the table column, and an indexed expression uses `BINARY`.
+    pub fn index_collations(&self, index_sql: &str) -> std::io::Result<Vec<Collation>> {
+        let tokens = tokenize(index_sql)?;
+        let columns = index_column_list(&tokens)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Expected column list in CREATE INDEX statement: {}", index_sql)))?;
+
+        Ok(columns.into_iter()
+            .map(|column| match column {
+                _ if column.iter().any(|token| token.is_keyword("COLLATE")) => parse_collation(column),
+                [Token::Word(name) | Token::String(name), rest @ ..] if rest.iter().all(|token| token.is_keyword("ASC") || token.is_keyword("DESC")) => {
+                    self.column_index(name).map_or(Collation::Binary, |index| self.columns[index].collation)
+                }
+                _ => Collation::Binary,
+            })
+            .collect())
+    }
+
+    /// Index of a column in the records stored in the table's b-tree, see [`TableDef::declared_order`],
+    /// or `None` for `VIRTUAL` generated columns, which are not stored.
+    pub fn storage_index(&self, index: usize) -> Option<usize> {
+        self.storage_order().iter().position(|&x| x == index)
+    }
+
+    /// Declared indices of the stored columns, in the order they are stored in.
+    fn storage_order(&self) -> Vec<usize> {
+        let is_stored = |index: &usize| self.columns[*index].generated != Some(Generated::Virtual);
+
+        if !self.without_rowid {
+            return (0..self.columns.len()).filter(is_stored).collect();
+        }
+
+        self.primary_key.iter().copied()
+            .chain((0..self.columns.len()).filter(|index| !self.primary_key.contains(index) && is_stored(index)))
+            .collect()
+    }
+
+    /// Whether the table has `VIRTUAL` generated columns, which its records leave out.
+    pub fn has_virtual_columns(&self) -> bool {
+        self.columns.iter().any(|column| column.generated == Some(Generated::Virtual))
+    }
+
+    /// Appends the default values of the columns missing from the end of a record in declared
+    /// order, written before the columns were added with `ALTER TABLE ADD COLUMN`, so that it has
+    /// an entry for every column. See [`ColumnDef::default_value`].
+    pub fn fill_defaults(&self, record: &mut Record) {
+        for column in self.columns.iter().skip(record.entries.len()) {
+            record.entries.push(column.default_value().unwrap_or(RecordEntry::Null));
+            if !record.serial_types.is_empty() {
+                record.serial_types.push(0);
+            }
+        }
+    }
+
+    /// Rearranges a record as stored in the table's b-tree into the declared column order.
+    ///
+    /// Rows of `WITHOUT ROWID` tables are stored with the primary key columns first,
+    /// followed by the remaining columns in declared order. `VIRTUAL` generated columns are
+    /// not stored and come out as NULL, as their expressions are not evaluated. Columns
+    /// missing from the record, as in rows written before an `ALTER TABLE ADD COLUMN`, come
+    /// out as their default value, see [`ColumnDef::default_value`]. Full records of other
+    /// tables are already in declared order and returned as they are.
+    pub fn declared_order(&self, record: Record) -> Record {
+        if !self.without_rowid && !self.has_virtual_columns() && record.entries.len() >= self.columns.len() {
+            return record;
+        }
+
+        let storage_order = self.storage_order();
+        let record_length = record.entries.len();
+        let mut entries = vec![RecordEntry::Null; self.columns.len()];
+        for (&index, entry) in storage_order.iter().zip(record.entries) {
+            entries[index] = entry;
+        }
+        for &index in storage_order.iter().skip(record_length) {
+            entries[index] = self.columns[index].default_value().unwrap_or(RecordEntry::Null);
+        }
+
+        let mut serial_types = vec![0; if record.serial_types.is_empty() { 0 } else { self.columns.len() }];
+        for (&index, serial_type) in storage_order.iter().zip(record.serial_types) {
+            serial_types[index] = serial_type;
+        }
+
+        Record {
+            entries,
+            serial_types,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(entries: Vec<RecordEntry>) -> Record {
+        Record {
+            entries,
+            serial_types: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn integer_primary_key_aliases_the_rowid() {
+        let table = TableDef::parse("CREATE TABLE t(name TEXT, id integer PRIMARY KEY)").unwrap();
+        assert_eq!(table.primary_key, [1]);
+        assert_eq!(table.rowid_alias(), Some(1));
+
+        // The payload holds NULL in place of the rowid
+        let stored = record(vec![RecordEntry::Text("a".to_string()), RecordEntry::Null]);
+        let values = table.primary_key_values(Some(5), &stored);
+        assert!(matches!(values.as_slice(), [RecordEntry::Integer(5)]));
+
+        let mut stored = stored;
+        table.set_rowid_alias(&mut stored, 5);
+        assert_eq!(stored.entries[1].as_i64(), Some(5));
+    }
+
+    #[test]
+    fn other_primary_keys_do_not_alias_the_rowid() {
+        let table = TableDef::parse("CREATE TABLE t(id INT PRIMARY KEY, name TEXT)").unwrap();
+        assert_eq!(table.primary_key, [0]);
+        assert_eq!(table.rowid_alias(), None);
+
+        let table = TableDef::parse("CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT) WITHOUT ROWID").unwrap();
+        assert_eq!(table.primary_key, [0]);
+        assert_eq!(table.rowid_alias(), None);
+
+        let table = TableDef::parse("CREATE TABLE t(id INTEGER, name TEXT)").unwrap();
+        assert!(table.primary_key.is_empty());
+        assert_eq!(table.rowid_alias(), None);
+    }
+
+    #[test]
+    fn composite_primary_keys_are_in_key_order() {
+        let table = TableDef::parse("CREATE TABLE t(a TEXT, b INTEGER, c, PRIMARY KEY(b, \"A\"))").unwrap();
+        assert_eq!(table.primary_key, [1, 0]);
+        assert_eq!(table.rowid_alias(), None);
+
+        let stored = record(vec![RecordEntry::Text("x".to_string()), RecordEntry::Integer(2), RecordEntry::Null]);
+        let values = table.primary_key_values(Some(9), &stored);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].as_i64(), Some(2));
+        assert_eq!(values[1].as_str(), Some("x"));
+    }
+
+    #[test]
+    fn not_null_and_default_constraints() {
+        let table = TableDef::parse("CREATE TABLE t(a INTEGER CONSTRAINT a_set NOT NULL DEFAULT -5, b TEXT DEFAULT 'it''s' NOT NULL, c DEFAULT CURRENT_TIMESTAMP, d REAL DEFAULT 0x10, e NULL)").unwrap();
+
+        let constraints = table.columns.iter()
+            .map(|column| (column.not_null, column.default.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(constraints, [
+            (true, Some("-5")),
+            (true, Some("'it''s'")),
+            (false, Some("CURRENT_TIMESTAMP")),
+            (false, Some("0x10")),
+            (false, None),
+        ]);
+
+        // Only constants are defaults of rows written before their column was added
+        assert_eq!(table.columns[0].default_value().unwrap().as_i64(), Some(-5));
+        assert_eq!(table.columns[1].default_value().unwrap().as_str(), Some("it's"));
+        assert!(table.columns[2].default_value().is_none());
+        assert!(matches!(table.columns[3].default_value(), Some(RecordEntry::Float(x)) if x == 16.0));
+        assert!(table.columns[4].default_value().is_none());
+    }
+}
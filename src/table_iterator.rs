@@ -0,0 +1,107 @@
+use std::io::{Error, ErrorKind, Read, Seek};
+use std::rc::Rc;
+
+use crate::{FilePage, Pager, Record};
+use crate::database::MAX_BTREE_DEPTH;
+use crate::FilePageType::{TableInterior, TableLeaf};
+
+/// Iterates over the rows of a table b-tree in rowid order.
+///
+/// Only the pages on the path from the root to the current leaf are held, and
+/// child pages are requested from the [`Pager`] as the traversal reaches them.
+pub struct TableIterator<'a, R> {
+    pager: &'a mut Pager<R>,
+    /// Pages on the path to the current leaf, each with the index of its next cell.
+    stack: Vec<(Rc<FilePage>, usize)>,
+    /// Page to descend into on the next step.
+    next_page: Option<u32>,
+    root_page: u32,
+    /// Pages read so far, which cannot exceed the size of the database unless the pages form a cycle.
+    pages_visited: u32,
+}
+
+impl<'a, R> TableIterator<'a, R>
+    where R: Read + Seek
+{
+    pub fn new(pager: &'a mut Pager<R>, root_page: u32) -> Self {
+        TableIterator {
+            pager,
+            stack: Vec::new(),
+            next_page: Some(root_page),
+            root_page,
+            pages_visited: 0,
+        }
+    }
+
+    fn advance(&mut self) -> std::io::Result<Option<(i64, Record)>> {
+        loop {
+            if let Some(page_number) = self.next_page.take() {
+                self.pages_visited += 1;
+                if self.pages_visited > self.pager.header().database_size() {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Table b-tree rooted at page {} has more pages than the database", self.root_page)));
+                }
+
+                // SQLite never builds b-trees this deep, so the pages must form a cycle
+                if self.stack.len() >= MAX_BTREE_DEPTH {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("B-tree is deeper than {} levels at page {}", MAX_BTREE_DEPTH, page_number)));
+                }
+
+                let page = self.pager.get(page_number)?;
+
+                match page.header.typ {
+                    TableInterior | TableLeaf => self.stack.push((page, 0)),
+                    ref x => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for a table b-tree", page_number, x))),
+                }
+            }
+
+            let Some((page, index)) = self.stack.last_mut() else {
+                return Ok(None);
+            };
+
+            let cell_index = *index;
+            *index += 1;
+
+            match (&page.header.typ, page.cells.get(cell_index)) {
+                (TableLeaf, Some(cell)) => {
+                    let malformed = |what: &str| Error::new(ErrorKind::InvalidData, format!("Table leaf cell {} has no {}", cell_index, what));
+                    let rowid = cell.rowid.ok_or_else(|| malformed("rowid"))?;
+                    let record = cell.payload.clone().ok_or_else(|| malformed("payload"))?;
+
+                    return Ok(Some((rowid, record)));
+                }
+                (TableInterior, Some(cell)) => {
+                    let left_child_page_number = cell.left_child_page_number
+                        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Table interior cell {} has no left child pointer", cell_index)))?;
+                    self.next_page = Some(self.pager.header().check_child_page(page.page_number, left_child_page_number)?);
+                }
+                (TableInterior, None) => {
+                    // The right-most child is the last one visited, so the page is no longer needed
+                    let (page, _) = self.stack.pop().unwrap();
+                    self.next_page = Some(self.pager.header().check_child_page(page.page_number, page.right_most_pointer()?)?);
+                }
+                _ => {
+                    // Also reached right away for a leaf without cells, such as the root page of an empty table
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<R> Iterator for TableIterator<'_, R>
+    where R: Read + Seek
+{
+    type Item = std::io::Result<(i64, Record)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.advance() {
+            Ok(row) => row.map(Ok),
+            Err(err) => {
+                // Stop after the first error rather than yielding a partial traversal
+                self.stack.clear();
+                self.next_page = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+
+use crate::FilePageType;
+
+/// What a lookup did on a page it visited, see [`TraceStep`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceDecision {
+    /// Descended into the left child of the cell at `cell_index`, the first cell of the interior
+    /// page whose key, the largest rowid of its left subtree, is not less than the rowid.
+    LeftChild {
+        cell_index: usize,
+        key: i64,
+        child_page_number: u32,
+    },
+    /// Descended into the right-most child, as every key of the interior page is less than
+    /// the rowid. `largest_key` is the key of the last cell, or `None` for a page without cells.
+    RightMostChild {
+        largest_key: Option<i64>,
+        child_page_number: u32,
+    },
+    /// Found the row in the cell at `cell_index` of the leaf page.
+    Found {
+        cell_index: usize,
+    },
+    /// Found no row with the rowid on the leaf page, where it would be stored in the cell
+    /// at `cell_index`.
+    NotFound {
+        cell_index: usize,
+    },
+}
+
+/// A page visited while looking a row up, see [`Database::find_row_traced`](crate::Database::find_row_traced).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    pub page_number: u32,
+    pub page_type: FilePageType,
+    pub cells_count: usize,
+    pub decision: TraceDecision,
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Page {} ({:?}, {} cells): ", self.page_number, self.page_type, self.cells_count)?;
+
+        match self.decision {
+            TraceDecision::LeftChild { cell_index, key, child_page_number } => {
+                write!(f, "cell {} has key {}, the first not less than the rowid, descending into its left child {}", cell_index, key, child_page_number)
+            }
+            TraceDecision::RightMostChild { largest_key: Some(key), child_page_number } => {
+                write!(f, "every key is less than the rowid, up to {}, descending into the right-most child {}", key, child_page_number)
+            }
+            TraceDecision::RightMostChild { largest_key: None, child_page_number } => {
+                write!(f, "no cells, descending into the right-most child {}", child_page_number)
+            }
+            TraceDecision::Found { cell_index } => write!(f, "found in cell {}", cell_index),
+            TraceDecision::NotFound { cell_index } => write!(f, "not found, would be in cell {}", cell_index),
+        }
+    }
+}
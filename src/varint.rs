@@ -0,0 +1,123 @@
+use std::io::Read;
+
+pub(crate) trait ReadVarExt: byteorder::ReadBytesExt {
+    /// Reads a SQLite variable-length integer. The first eight bytes contribute their lower
+    /// seven bits each, while the ninth byte, if reached, contributes all eight of its bits.
+    ///
+    /// The resulting 64 bits are the two's complement representation of the value, so
+    /// negative values, which always take all nine bytes, decode to the negative `i64`.
+    fn read_var64(&mut self) -> std::io::Result<i64> {
+        let mut res = 0u64;
+
+        for _ in 0..8 {
+            let val = self.read_u8()? as u64;
+
+            res = (res << 7) | (val & 0x7F);
+
+            if val & 0x80 == 0 {
+                return Ok(res as i64);
+            }
+        }
+
+        let val = self.read_u8()? as u64;
+
+        Ok(((res << 8) | val) as i64)
+    }
+}
+
+impl<R: Read> ReadVarExt for R {}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadVarExt;
+
+    /// Reads a varint that must take up all of the given bytes.
+    fn read(bytes: &[u8]) -> i64 {
+        let mut reader = bytes;
+        let value = reader.read_var64().unwrap();
+        assert!(reader.is_empty(), "{} bytes left after reading {:02x?}", reader.len(), bytes);
+        value
+    }
+
+    #[test]
+    fn one_byte_varints() {
+        assert_eq!(read(&[0x00]), 0);
+        assert_eq!(read(&[0x01]), 1);
+        assert_eq!(read(&[0x7f]), 127);
+    }
+
+    #[test]
+    fn two_byte_varints() {
+        assert_eq!(read(&[0x81, 0x00]), 128);
+        assert_eq!(read(&[0xff, 0x7f]), 16383);
+    }
+
+    #[test]
+    fn eight_byte_varints() {
+        assert_eq!(read(&[0x81, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]), 1 << 49);
+        assert_eq!(read(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]), (1 << 56) - 1);
+    }
+
+    #[test]
+    fn ninth_byte_contributes_all_eight_bits() {
+        assert_eq!(read(&[0x80, 0xc0, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]), 1 << 56);
+        assert_eq!(read(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xff]), 0xff);
+    }
+
+    #[test]
+    fn values_above_2_pow_63_wrap_to_negative() {
+        assert_eq!(read(&[0xc0, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]) as u64, 1 << 63);
+        assert_eq!(read(&[0xc0, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]) as u64, (1 << 63) + 1);
+        assert_eq!(read(&[0xff; 9]) as u64, 0xFFFF_FFFF_FFFF_FFFF);
+        assert_eq!(read(&[0xff; 9]), -1);
+    }
+
+    /// Encodes a value the way SQLite's `putVarint` does.
+    fn write(value: i64) -> Vec<u8> {
+        let mut value = value as u64;
+
+        if value >> 56 != 0 {
+            let mut bytes = vec![0; 9];
+            bytes[8] = value as u8;
+            value >>= 8;
+            for byte in bytes[..8].iter_mut().rev() {
+                *byte = (value & 0x7f) as u8 | 0x80;
+                value >>= 7;
+            }
+            return bytes;
+        }
+
+        let mut bytes = vec![(value & 0x7f) as u8];
+        value >>= 7;
+        while value != 0 {
+            bytes.push((value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes.reverse();
+        bytes
+    }
+
+    #[test]
+    fn negative_values_round_trip_as_nine_byte_varints() {
+        for value in [-1, -2, i64::MIN, i64::MIN + 1, -(1 << 56)] {
+            let bytes = write(value);
+            assert_eq!(bytes.len(), 9);
+            assert_eq!(read(&bytes), value);
+        }
+    }
+
+    #[test]
+    fn values_round_trip() {
+        for value in [0, 127, 128, 16383, 16384, (1 << 56) - 1, 1 << 56, i64::MAX] {
+            assert_eq!(read(&write(value)), value);
+        }
+    }
+
+    #[test]
+    fn stops_after_nine_bytes() {
+        let bytes = [0xff; 10];
+        let mut reader = &bytes[..];
+        assert_eq!(reader.read_var64().unwrap(), -1);
+        assert_eq!(reader.len(), 1);
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+const WAL_MAGIC: u32 = 0x377f0682;
+const WAL_HEADER_SIZE: u64 = 32;
+const WAL_FRAME_HEADER_SIZE: u64 = 24;
+
+pub(crate) trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// A write-ahead log holding pages that were committed but not yet checkpointed into the
+/// main database file.
+///
+/// Only frames that belong to the last valid commit are used: a frame is valid if its salt
+/// matches the WAL header and its checksum, which covers all preceding frames, matches.
+/// Frames written after the last commit frame belong to an unfinished transaction.
+pub struct Wal {
+    reader: Box<dyn ReadSeek>,
+    page_size: u32,
+    /// Offset of the page data of the latest committed frame of each page.
+    frames: HashMap<u32, u64>,
+    /// Size of the database in pages after the last commit, if there is one.
+    database_size: Option<u32>,
+}
+
+impl Wal {
+    pub fn open<P>(path: P) -> std::io::Result<Self>
+        where P: AsRef<Path>
+    {
+        Wal::from_reader(File::open(path)?)
+    }
+
+    pub fn from_reader<W>(mut reader: W) -> std::io::Result<Self>
+        where W: Read + Seek + 'static
+    {
+        let mut header = [0; WAL_HEADER_SIZE as usize];
+        reader.seek(SeekFrom::Start(0))?;
+
+        // A WAL that was reset after a checkpoint may be empty, which is the same as having no frames
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(Wal::empty(reader)),
+            Err(err) => return Err(err),
+        }
+
+        let magic = BigEndian::read_u32(&header[0..4]);
+
+        // The lowest bit of the magic number selects the byte order of the checksums
+        let big_endian_checksum = magic & 1 == 1;
+        let page_size = BigEndian::read_u32(&header[8..12]);
+        let salt = (BigEndian::read_u32(&header[16..20]), BigEndian::read_u32(&header[20..24]));
+        let header_checksum = (BigEndian::read_u32(&header[24..28]), BigEndian::read_u32(&header[28..32]));
+
+        // Like SQLite, a WAL with an invalid magic number or page size is ignored, so that a
+        // stray file next to the database does not keep it from being read
+        if magic & !1 != WAL_MAGIC || !page_size.is_power_of_two() || !(512..=65536).contains(&page_size) {
+            return Ok(Wal::empty(reader));
+        }
+
+        let mut wal = Wal {
+            reader: Box::new(reader),
+            page_size,
+            frames: HashMap::new(),
+            database_size: None,
+        };
+
+        // A WAL with an invalid header checksum has no valid frames, as if it was empty
+        let mut checksum = wal_checksum(&header[0..24], big_endian_checksum, (0, 0));
+        if checksum != header_checksum {
+            return Ok(wal);
+        }
+
+        let mut frame_header = [0; WAL_FRAME_HEADER_SIZE as usize];
+        let mut frame_data = vec![0; page_size as usize];
+        let mut uncommitted = Vec::new();
+        let mut offset = WAL_HEADER_SIZE;
+
+        loop {
+            let read = wal.reader.read_exact(&mut frame_header).and_then(|_| wal.reader.read_exact(&mut frame_data));
+
+            match read {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+
+            let page_number = BigEndian::read_u32(&frame_header[0..4]);
+            let database_size = BigEndian::read_u32(&frame_header[4..8]);
+            let frame_salt = (BigEndian::read_u32(&frame_header[8..12]), BigEndian::read_u32(&frame_header[12..16]));
+            let frame_checksum = (BigEndian::read_u32(&frame_header[16..20]), BigEndian::read_u32(&frame_header[20..24]));
+
+            checksum = wal_checksum(&frame_header[0..8], big_endian_checksum, checksum);
+            checksum = wal_checksum(&frame_data, big_endian_checksum, checksum);
+
+            if page_number == 0 || frame_salt != salt || frame_checksum != checksum {
+                break;
+            }
+
+            uncommitted.push((page_number, offset + WAL_FRAME_HEADER_SIZE));
+
+            // A non-zero database size marks the last frame of a transaction
+            if database_size != 0 {
+                wal.frames.extend(uncommitted.drain(..));
+                wal.database_size = Some(database_size);
+            }
+
+            offset += WAL_FRAME_HEADER_SIZE + page_size as u64;
+        }
+
+        Ok(wal)
+    }
+
+    /// A WAL without frames, whose pages are all read from the database file.
+    fn empty<W>(reader: W) -> Self
+        where W: Read + Seek + 'static
+    {
+        Wal {
+            reader: Box::new(reader),
+            page_size: 0,
+            frames: HashMap::new(),
+            database_size: None,
+        }
+    }
+
+    /// Page size recorded in the WAL header, or 0 if the WAL is empty.
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// Size of the database in pages as of the last commit in the WAL.
+    pub fn database_size(&self) -> Option<u32> {
+        self.database_size
+    }
+
+    /// Checks whether the WAL holds a committed version of the given page.
+    pub fn contains(&self, page_number: u32) -> bool {
+        self.frames.contains_key(&page_number)
+    }
+
+    /// Number of distinct pages with a committed version in the WAL.
+    pub fn page_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Reads bytes of the committed version of a page, starting at `offset` within the page.
+    /// Returns `None` if the page is not in the WAL.
+    pub(crate) fn read_page(&mut self, page_number: u32, offset: u64, buf: &mut [u8]) -> Option<std::io::Result<usize>> {
+        let frame_offset = *self.frames.get(&page_number)?;
+        let length = buf.len().min((self.page_size as u64 - offset) as usize);
+
+        Some(self.reader.seek(SeekFrom::Start(frame_offset + offset))
+            .and_then(|_| self.reader.read(&mut buf[..length])))
+    }
+
+    /// Largest page number with a committed version in the WAL.
+    pub(crate) fn max_page_number(&self) -> Option<u32> {
+        self.frames.keys().max().copied()
+    }
+}
+
+impl fmt::Debug for Wal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Wal")
+            .field("page_size", &self.page_size)
+            .field("page_count", &self.frames.len())
+            .field("database_size", &self.database_size)
+            .finish()
+    }
+}
+
+/// Computes the cumulative checksum used by the WAL header and frames.
+fn wal_checksum(data: &[u8], big_endian: bool, (mut s0, mut s1): (u32, u32)) -> (u32, u32) {
+    for chunk in data.chunks_exact(8) {
+        let (x0, x1) = if big_endian {
+            (BigEndian::read_u32(&chunk[0..4]), BigEndian::read_u32(&chunk[4..8]))
+        } else {
+            (LittleEndian::read_u32(&chunk[0..4]), LittleEndian::read_u32(&chunk[4..8]))
+        };
+
+        s0 = s0.wrapping_add(x0).wrapping_add(s1);
+        s1 = s1.wrapping_add(x1).wrapping_add(s0);
+    }
+
+    (s0, s1)
+}
+
+/// Reads the main database file with the committed pages of a WAL laid over it, so that
+/// everything reading through it sees the latest committed version of each page.
+#[derive(Debug)]
+pub(crate) struct WalReader<R> {
+    reader: R,
+    wal: Option<Wal>,
+    position: u64,
+}
+
+impl<R> WalReader<R>
+    where R: Read + Seek
+{
+    pub(crate) fn new(reader: R, wal: Option<Wal>) -> Self {
+        WalReader {
+            reader,
+            wal: wal.filter(|wal| wal.page_count() > 0),
+            position: 0,
+        }
+    }
+
+    pub(crate) fn wal(&self) -> Option<&Wal> {
+        self.wal.as_ref()
+    }
+}
+
+impl<R> Read for WalReader<R>
+    where R: Read + Seek
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(wal) = &mut self.wal else {
+            let read = self.reader.read(buf)?;
+            self.position += read as u64;
+            return Ok(read);
+        };
+
+        let page_size = wal.page_size as u64;
+        let page_number = u32::try_from(self.position / page_size + 1)
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("Offset {} is beyond the last page", self.position)))?;
+        let offset = self.position % page_size;
+
+        // Never read across a page boundary, since the next page may come from elsewhere
+        let length = buf.len().min((page_size - offset) as usize);
+
+        let read = match wal.read_page(page_number, offset, &mut buf[..length]) {
+            Some(read) => read?,
+            None => {
+                self.reader.seek(SeekFrom::Start(self.position))?;
+                self.reader.read(&mut buf[..length])?
+            }
+        };
+
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R> Seek for WalReader<R>
+    where R: Read + Seek
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let Some(wal) = &self.wal else {
+            self.position = self.reader.seek(pos)?;
+            return Ok(self.position);
+        };
+
+        self.position = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Invalid seek to a negative position"))?,
+            SeekFrom::End(offset) => {
+                let wal_end = wal.max_page_number().map_or(0, |page_number| page_number as u64 * wal.page_size as u64);
+                let end = self.reader.seek(SeekFrom::End(0))?.max(wal_end);
+                end.checked_add_signed(offset)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Invalid seek to a negative position"))?
+            }
+        };
+
+        Ok(self.position)
+    }
+}
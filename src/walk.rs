@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Read, Seek};
+use std::ops::ControlFlow;
+
+use crate::{FilePage, Filter, Pager, Record};
+use crate::database::MAX_BTREE_DEPTH;
+use crate::FilePageType::{IndexInterior, IndexLeaf, TableInterior, TableLeaf};
+
+/// Outcome of [`walk_table`] or [`walk_index`], or of `Database::scan_parallel` with the `parallel` feature.
+#[derive(Debug, Default)]
+pub struct WalkSummary {
+    /// Number of pages the walk reached, including the ones that failed to parse.
+    pub pages_visited: usize,
+    /// Errors of the pages that could not be read or were malformed.
+    pub errors: Vec<Error>,
+    /// Whether the visitor stopped the walk with [`ControlFlow::Break`] before the end of the b-tree.
+    pub stopped: bool,
+}
+
+/// Walks the table b-tree rooted at `root_page` and calls `visitor` with the rowid and record
+/// of every row matching `filter`, in rowid order, until it returns [`ControlFlow::Break`].
+/// The walk then returns without reading any other page, so finding the first matching row
+/// only reads the pages on the way to it.
+///
+/// A page that cannot be read or is malformed does not stop the walk: its error is collected
+/// in the summary and the rest of the b-tree is still visited. Errors returned by the visitor
+/// other than [`ErrorKind::InvalidData`] stop the walk and are returned as they are.
+pub fn walk_table<R, F>(pager: &mut Pager<R>, root_page: u32, filter: &Filter, mut visitor: F) -> std::io::Result<WalkSummary>
+    where R: Read + Seek,
+          F: FnMut(i64, &Record) -> std::io::Result<ControlFlow<()>>
+{
+    let mut walker = Walker {
+        filter,
+        visitor: Visitor::Table(&mut visitor),
+        summary: WalkSummary::default(),
+        visited: HashSet::new(),
+    };
+
+    walker.walk_page(pager, root_page, None, 0)?;
+    Ok(walker.summary)
+}
+
+/// Walks the index b-tree rooted at `root_page` and calls `visitor` with every entry matching
+/// the column predicates of `filter`, in key order, until it returns [`ControlFlow::Break`].
+/// This also walks the rows of a `WITHOUT ROWID` table, with their columns in storage order.
+///
+/// Errors are handled as in [`walk_table`]. The rowid range of the filter is ignored, as index
+/// entries have no rowid of their own.
+pub fn walk_index<R, F>(pager: &mut Pager<R>, root_page: u32, filter: &Filter, mut visitor: F) -> std::io::Result<WalkSummary>
+    where R: Read + Seek,
+          F: FnMut(&Record) -> std::io::Result<ControlFlow<()>>
+{
+    let mut walker = Walker {
+        filter,
+        visitor: Visitor::Index(&mut visitor),
+        summary: WalkSummary::default(),
+        visited: HashSet::new(),
+    };
+
+    walker.walk_page(pager, root_page, None, 0)?;
+    Ok(walker.summary)
+}
+
+enum Visitor<'a> {
+    Table(&'a mut dyn FnMut(i64, &Record) -> std::io::Result<ControlFlow<()>>),
+    Index(&'a mut dyn FnMut(&Record) -> std::io::Result<ControlFlow<()>>),
+}
+
+struct Walker<'a> {
+    filter: &'a Filter,
+    visitor: Visitor<'a>,
+    /// Pages past the last visited entry are never read once the summary says the walk stopped.
+    summary: WalkSummary,
+    /// Pages already reached, as a page referenced twice means the b-tree has a cycle.
+    visited: HashSet<u32>,
+}
+
+impl Walker<'_> {
+    fn walk_page<R>(&mut self, pager: &mut Pager<R>, page_number: u32, referenced_from: Option<u32>, depth: usize) -> std::io::Result<()>
+        where R: Read + Seek
+    {
+        if self.summary.stopped {
+            return Ok(());
+        }
+
+        self.summary.pages_visited += 1;
+
+        if let Some(parent) = referenced_from {
+            if let Err(err) = pager.header().check_child_page(parent, page_number) {
+                self.summary.errors.push(err);
+                return Ok(());
+            }
+        }
+
+        // SQLite never builds b-trees this deep, so the pages must form a cycle
+        if depth > MAX_BTREE_DEPTH {
+            self.summary.errors.push(Error::new(ErrorKind::InvalidData, format!("B-tree is deeper than {} levels at page {}", MAX_BTREE_DEPTH, page_number)));
+            return Ok(());
+        }
+
+        if !self.visited.insert(page_number) {
+            self.summary.errors.push(Error::new(ErrorKind::InvalidData, format!("Page {} points to page {} which was already visited", referenced_from.unwrap_or(page_number), page_number)));
+            return Ok(());
+        }
+
+        let page = match pager.get(page_number) {
+            Ok(page) => page,
+            Err(err) => {
+                let offset = pager.header().page_offset(page_number) + FilePage::header_start(page_number) as u64;
+                self.summary.errors.push(match referenced_from {
+                    Some(parent) => Error::new(err.kind(), format!("Page {} points to page {} at offset {} which could not be read: {}", parent, page_number, offset, err)),
+                    None => Error::new(err.kind(), format!("Page {} at offset {} could not be read: {}", page_number, offset, err)),
+                });
+                return Ok(());
+            }
+        };
+
+        match self.walk_cells(pager, &page, depth) {
+            Err(err) if err.kind() == ErrorKind::InvalidData => {
+                self.summary.errors.push(err);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn walk_cells<R>(&mut self, pager: &mut Pager<R>, page: &FilePage, depth: usize) -> std::io::Result<()>
+        where R: Read + Seek
+    {
+        let filter = self.filter;
+        let page_number = page.page_number;
+
+        match (&page.header.typ, &mut self.visitor) {
+            (TableInterior, Visitor::Table(_)) => {
+                // Only descend into the children whose range of rowids overlaps the filter
+                let mut after = None;
+                for (index, cell) in page.cells.iter().enumerate() {
+                    let key = cell.rowid.ok_or_else(|| malformed_cell(page_number, index, "rowid"))?;
+                    let left_child_page_number = cell.left_child_page_number.ok_or_else(|| malformed_cell(page_number, index, "left child pointer"))?;
+                    if filter.overlaps_rowids(after, Some(key)) {
+                        self.walk_page(pager, left_child_page_number, Some(page_number), depth + 1)?;
+                    }
+                    if self.summary.stopped {
+                        return Ok(());
+                    }
+                    after = Some(key);
+                }
+                if filter.overlaps_rowids(after, None) {
+                    self.walk_page(pager, page.right_most_pointer()?, Some(page_number), depth + 1)?;
+                }
+            }
+            (TableLeaf, Visitor::Table(visitor)) => {
+                for (index, cell) in page.cells.iter().enumerate().filter(|(_, cell)| filter.matches(cell)) {
+                    let rowid = cell.rowid.ok_or_else(|| malformed_cell(page_number, index, "rowid"))?;
+                    let record = cell.payload.as_ref().ok_or_else(|| malformed_cell(page_number, index, "payload"))?;
+                    if visitor(rowid, record)?.is_break() {
+                        self.summary.stopped = true;
+                        break;
+                    }
+                }
+            }
+            (IndexInterior, Visitor::Index(_)) => {
+                // Each cell holds an entry that comes after the entries of its left subtree
+                for (index, cell) in page.cells.iter().enumerate() {
+                    let left_child_page_number = cell.left_child_page_number.ok_or_else(|| malformed_cell(page_number, index, "left child pointer"))?;
+                    self.walk_page(pager, left_child_page_number, Some(page_number), depth + 1)?;
+
+                    let record = cell.payload.as_ref().ok_or_else(|| malformed_cell(page_number, index, "payload"))?;
+                    if self.visit_index_entry(record)? {
+                        break;
+                    }
+                }
+                self.walk_page(pager, page.right_most_pointer()?, Some(page_number), depth + 1)?;
+            }
+            (IndexLeaf, Visitor::Index(_)) => {
+                for (index, cell) in page.cells.iter().enumerate() {
+                    let record = cell.payload.as_ref().ok_or_else(|| malformed_cell(page_number, index, "payload"))?;
+                    if self.visit_index_entry(record)? {
+                        break;
+                    }
+                }
+            }
+            (x, Visitor::Table(_)) => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for a table b-tree", page_number, x))),
+            (x, Visitor::Index(_)) => return Err(Error::new(ErrorKind::InvalidData, format!("Page {} has unexpected type {:?} for an index b-tree", page_number, x))),
+        }
+
+        Ok(())
+    }
+
+    /// Passes an index entry matching the filter to the visitor. Returns whether the walk stopped.
+    fn visit_index_entry(&mut self, record: &Record) -> std::io::Result<bool> {
+        if self.summary.stopped {
+            return Ok(true);
+        }
+
+        if let Visitor::Index(visitor) = &mut self.visitor {
+            if self.filter.matches_record(record) && visitor(record)?.is_break() {
+                self.summary.stopped = true;
+            }
+        }
+
+        Ok(self.summary.stopped)
+    }
+}
+
+fn malformed_cell(page_number: u32, cell_index: usize, what: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Cell {} of page {} has no {}", cell_index, page_number, what))
+}
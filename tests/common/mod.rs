@@ -0,0 +1,37 @@
+//! Helpers shared by the integration tests, which read the databases in `tests/fixtures`.
+
+#![allow(dead_code)]
+
+use std::fs::File;
+
+use sqlite_reader::Database;
+
+/// Path of a database built by `tests/fixtures/generate.py`.
+pub fn fixture_path(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+pub fn open(name: &str) -> Database<File> {
+    Database::open(fixture_path(name)).unwrap()
+}
+
+/// Reads a database into memory, so that a test can corrupt it before opening it with
+/// [`Database::from_bytes`].
+pub fn read(name: &str) -> Vec<u8> {
+    std::fs::read(fixture_path(name)).unwrap()
+}
+
+/// Same text as `digits` in `tests/fixtures/generate.py`.
+pub fn digits(length: usize) -> String {
+    (0..=length / 5).map(|index| format!("{:05}", index)).collect::<String>()[..length].to_string()
+}
+
+/// Copies a fixture into a fresh temporary directory, so that a test can put files next to
+/// it, and returns the path of the copy.
+pub fn copy(name: &str, test: &str) -> String {
+    let directory = std::env::temp_dir().join(format!("sqlite-reader-{}-{}", std::process::id(), test));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    std::fs::copy(fixture_path(name), &path).unwrap();
+    path.to_str().unwrap().to_string()
+}
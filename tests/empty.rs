@@ -0,0 +1,50 @@
+mod common;
+
+use std::ops::ControlFlow;
+use std::process::Command;
+
+use sqlite_reader::{walk_table, FilePageType, Filter, Schema, SchemaEntryType, SCHEMA_ROOT_PAGE};
+
+#[test]
+fn page_1_is_the_only_page() {
+    let mut database = common::open("schema_only.db");
+    assert_eq!(database.header().database_size(), 1);
+
+    let pages = database.pages().collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(pages.iter().map(|(page_number, _)| *page_number).collect::<Vec<_>>(), [1]);
+    assert_eq!(pages[0].1.header.typ, FilePageType::TableLeaf);
+
+    let schema = Schema::read(&mut database).unwrap();
+    assert_eq!(schema.entries.len(), 1);
+    assert_eq!(schema.entries[0].typ, SchemaEntryType::View);
+    assert_eq!(schema.entries[0].root_page, None);
+    assert_eq!(database.tables().unwrap().len(), 0);
+
+    let summary = walk_table(database.pager(), SCHEMA_ROOT_PAGE, &Filter::default(), |_, _| Ok(ControlFlow::Continue(()))).unwrap();
+    assert_eq!(summary.pages_visited, 1);
+    assert!(summary.errors.is_empty());
+    assert!(database.integrity_check().is_empty());
+}
+
+#[test]
+fn empty_table_has_no_rows() {
+    let mut database = common::open("empty.db");
+    assert_eq!(database.header().database_size(), 2);
+
+    let mut table = database.table("t").unwrap().unwrap();
+    assert_eq!(table.root_page, 2);
+    assert_eq!(table.rows().count(), 0);
+    assert!(database.integrity_check().is_empty());
+}
+
+#[test]
+fn empty_table_prints_no_rows() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sqlite-reader"))
+        .args([common::fixture_path("empty.db").as_str(), "--table", "t", "--format", "csv"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "a,b\r\n");
+    assert!(output.stderr.is_empty());
+}
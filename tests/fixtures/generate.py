@@ -0,0 +1,257 @@
+#!/usr/bin/env python3
+"""Builds the databases read by the integration tests with the sqlite3 module.
+
+Run from any directory to recreate every fixture next to this script. The databases are
+committed, so this is only needed when adding or changing one.
+"""
+
+import ctypes
+import os
+import sqlite3
+
+FIXTURES = []
+
+
+def fixture(function):
+    FIXTURES.append(function)
+    return function
+
+
+def fixture_path(name):
+    return os.path.join(os.path.dirname(os.path.abspath(__file__)), name)
+
+
+def connect(name, page_size=512):
+    path = fixture_path(name)
+    if os.path.exists(path):
+        os.remove(path)
+    connection = sqlite3.connect(path)
+    connection.execute(f"PRAGMA page_size = {page_size}")
+    return connection
+
+
+def digits(length):
+    """Text whose every position can be told apart, so that misplaced chunks show up."""
+    return "".join(f"{index:05}" for index in range(length // 5 + 1))[:length]
+
+
+@fixture
+def overflow():
+    connection = connect("overflow.db")
+    connection.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, s TEXT, b BLOB)")
+    connection.execute("INSERT INTO t VALUES (1, ?, NULL)", (digits(5000),))
+    connection.execute("INSERT INTO t VALUES (2, NULL, ?)", (bytes(index % 251 for index in range(3000)),))
+    connection.execute("INSERT INTO t VALUES (3, 'short', x'00')")
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def negative():
+    connection = connect("negative.db")
+    connection.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, n INTEGER)")
+    for value in [-(1 << 63), -1, 0, 1, (1 << 63) - 1]:
+        connection.execute("INSERT INTO t VALUES (?, ?)", (value, value))
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def large_pages():
+    connection = connect("large_pages.db", page_size=65536)
+    connection.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, s TEXT)")
+    connection.executemany("INSERT INTO t VALUES (?, ?)", [(index, digits(100)) for index in range(1, 101)])
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def csv():
+    connection = connect("csv.db")
+    connection.execute("CREATE TABLE q(id INTEGER PRIMARY KEY, s TEXT, b BLOB, n)")
+    connection.executemany("INSERT INTO q VALUES (?, ?, ?, ?)", [
+        (1, "plain", b"\x00\xff", None),
+        (2, "a,b", b"", 1.5),
+        (3, 'say "hi"', None, -3),
+        (4, "two\nlines", None, None),
+    ])
+    connection.execute("CREATE TABLE w(k TEXT, v INTEGER, d AS (v * 2), PRIMARY KEY (v, k)) WITHOUT ROWID")
+    connection.executemany("INSERT INTO w VALUES (?, ?)", [("x", 2), ("y", 1)])
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def reserved():
+    # Python cannot set the reserved space, so this goes through the SQLite library it loaded
+    # with SQLITE_FCNTL_RESERVE_BYTES, which takes effect when the database is vacuumed
+    connect("reserved.db", page_size=1024).close()
+    path = next(line.split()[-1] for line in open("/proc/self/maps") if "libsqlite3" in line)
+    library = ctypes.CDLL(path)
+    database = ctypes.c_void_p()
+    assert library.sqlite3_open(fixture_path("reserved.db").encode(), ctypes.byref(database)) == 0
+
+    def execute(sql):
+        assert library.sqlite3_exec(database, sql.encode(), None, None, None) == 0, sql
+
+    execute("PRAGMA page_size = 1024")
+    execute("CREATE TABLE t(id INTEGER PRIMARY KEY, s TEXT)")
+    reserved_bytes = ctypes.c_int(32)
+    assert library.sqlite3_file_control(database, b"main", 38, ctypes.byref(reserved_bytes)) == 0
+    execute("VACUUM")
+    execute("BEGIN")
+    for index in range(1, 101):
+        execute(f"INSERT INTO t VALUES ({index}, '{digits(index * 23)}')")
+    execute("COMMIT")
+    library.sqlite3_close(database)
+
+
+@fixture
+def range_():
+    connection = connect("range.db")
+    connection.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, s TEXT)")
+    connection.executemany("INSERT INTO t VALUES (?, ?)", [(index, digits(40)) for index in range(1, 501)])
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def empty():
+    connection = connect("empty.db")
+    connection.execute("CREATE TABLE t(a INTEGER, b TEXT)")
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def schema_only():
+    # A view has no b-tree of its own, so the whole database fits in page 1
+    connection = connect("schema_only.db")
+    connection.execute("CREATE VIEW v AS SELECT 1 AS a")
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def descending():
+    connection = connect("descending.db")
+    connection.execute("CREATE TABLE t(a TEXT, b INTEGER)")
+    connection.execute("CREATE INDEX t_a_b ON t(a DESC, b)")
+    connection.executemany("INSERT INTO t VALUES (?, ?)", [(f"{index % 50:03}", index) for index in range(1, 501)])
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def index_overflow():
+    # Index keys of 1000 bytes spill to overflow pages, on interior pages as well as on leaves
+    connection = connect("index_overflow.db")
+    connection.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, s TEXT)")
+    connection.execute("CREATE INDEX t_s ON t(s)")
+    connection.executemany("INSERT INTO t VALUES (?, ?)", [(index, f"{index:03}" + digits(997)) for index in range(1, 21)])
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def tagged():
+    connection = connect("tagged.db")
+    # The application id of GeoPackage, "GPKG" in ASCII
+    connection.execute("PRAGMA application_id = 1196444487")
+    connection.execute("PRAGMA user_version = 10300")
+    connection.execute("CREATE TABLE t(a)")
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def rowid_alias():
+    # The INTEGER PRIMARY KEY column is neither the first one nor in the order of insertion
+    connection = connect("rowid_alias.db")
+    connection.execute("CREATE TABLE t(name TEXT, id INTEGER PRIMARY KEY, n INTEGER)")
+    connection.executemany("INSERT INTO t VALUES (?, ?, ?)", [("b", 20, 1), ("a", 10, None), ("c", -3, 2)])
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def catalog():
+    connection = connect("catalog.db")
+    connection.executescript("""
+        CREATE TABLE users(id INTEGER PRIMARY KEY AUTOINCREMENT, email TEXT UNIQUE, name TEXT COLLATE NOCASE);
+        CREATE TABLE posts(id INTEGER PRIMARY KEY, user_id INTEGER, title TEXT, created REAL);
+        CREATE INDEX posts_user ON posts(user_id);
+        CREATE INDEX posts_user_created ON posts(user_id, created DESC);
+        CREATE VIEW titles AS SELECT title FROM posts;
+        CREATE TRIGGER users_delete AFTER DELETE ON users BEGIN DELETE FROM posts WHERE user_id = old.id; END;
+        INSERT INTO users(email, name) VALUES ('alice@example.com', 'Alice'), ('bob@example.com', 'Bob');
+        INSERT INTO posts VALUES (1, 1, 'Hello', 1.5), (2, 2, 'World', 2.5);
+    """)
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def generated():
+    connection = connect("generated.db")
+    connection.execute("""
+        CREATE TABLE t(
+            a INTEGER,
+            b INTEGER GENERATED ALWAYS AS (a * 2) VIRTUAL,
+            c TEXT,
+            d TEXT GENERATED ALWAYS AS (c || '!') STORED,
+            e TEXT
+        )
+    """)
+    connection.executemany("INSERT INTO t(a, c, e) VALUES (?, ?, ?)", [(1, "x", "p"), (2, "y", "q")])
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def collations():
+    connection = connect("collations.db")
+    connection.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT COLLATE NOCASE)")
+    connection.execute("CREATE INDEX t_name ON t(name)")
+    connection.execute("CREATE INDEX t_name_rtrim ON t(name COLLATE RTRIM)")
+    connection.executemany("INSERT INTO t VALUES (?, ?)", [(1, "Alice"), (2, "bob"), (3, "ALICE"), (4, "Carol"), (5, "alice  ")])
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def added_columns():
+    # The first two rows are written before the columns are added, and keep their short records
+    connection = connect("added_columns.db")
+    connection.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, a TEXT)")
+    connection.executemany("INSERT INTO t(a) VALUES (?)", [("x",), ("y",)])
+    connection.execute("ALTER TABLE t ADD COLUMN b INTEGER NOT NULL DEFAULT 7")
+    connection.execute("ALTER TABLE t ADD COLUMN c TEXT DEFAULT 'none'")
+    connection.execute("ALTER TABLE t ADD COLUMN d REAL DEFAULT (-1)")
+    connection.execute("ALTER TABLE t ADD COLUMN e")
+    connection.execute("INSERT INTO t(a, b, c, d, e) VALUES ('z', 8, 'some', 2.5, x'01')")
+    connection.commit()
+    connection.close()
+
+
+@fixture
+def autovacuum():
+    # A row that overflows, and enough rows for an interior page, so that the pointer map
+    # lists every kind of page. Deleted rows leave free pages only in incremental mode
+    for mode in ["full", "incremental"]:
+        connection = connect(f"autovacuum_{mode}.db")
+        connection.execute(f"PRAGMA auto_vacuum = {mode}")
+        connection.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, s TEXT)")
+        connection.execute("CREATE TABLE u(id INTEGER PRIMARY KEY, s TEXT)")
+        connection.execute("INSERT INTO t VALUES (1, ?)", (digits(1500),))
+        connection.executemany("INSERT INTO t VALUES (?, ?)", [(index, digits(40)) for index in range(2, 41)])
+        connection.executemany("INSERT INTO u VALUES (?, ?)", [(index, digits(200)) for index in range(1, 11)])
+        connection.commit()
+        connection.execute("DELETE FROM u WHERE id > 2")
+        connection.commit()
+        connection.close()
+
+
+if __name__ == "__main__":
+    for function in FIXTURES:
+        function()
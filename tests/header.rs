@@ -0,0 +1,65 @@
+mod common;
+
+use std::io::ErrorKind;
+
+use sqlite_reader::{Database, Row};
+
+#[test]
+fn pages_of_64_kib() {
+    let mut database = common::open("large_pages.db");
+    assert_eq!(database.header().page_size(), 65536);
+    assert_eq!(database.header().database_size(), 2);
+
+    let mut table = database.table("t").unwrap().unwrap();
+    let rows = table.rows().collect::<std::io::Result<Vec<Row>>>().unwrap();
+    assert_eq!(rows.len(), 100);
+    assert_eq!(rows[99].rowid, Some(100));
+    assert_eq!(rows[99].record.entries[1].as_str().map(str::len), Some(100));
+}
+
+#[test]
+fn reserved_space_at_the_end_of_pages() {
+    let mut database = common::open("reserved.db");
+    assert_eq!(database.header().reserved_space(), 32);
+    assert_eq!(database.header().usable_size(), 992);
+
+    let mut table = database.table("t").unwrap().unwrap();
+    let rows = table.rows().collect::<std::io::Result<Vec<Row>>>().unwrap();
+    assert_eq!(rows.len(), 100);
+    for row in rows {
+        let expected = common::digits(row.rowid.unwrap() as usize * 23);
+        assert_eq!(row.record.entries[1].as_str(), Some(expected.as_str()));
+    }
+
+    assert!(database.integrity_check().is_empty());
+}
+
+#[test]
+fn text_encoding_0_is_an_error_unless_read_as_utf8() {
+    let mut data = common::read("csv.db");
+    data[56..60].copy_from_slice(&0u32.to_be_bytes());
+
+    // The header itself is read, but not the text of the schema
+    let mut database = Database::from_bytes(&data).unwrap();
+    assert_eq!(database.header().text_encoding(), 0);
+    let err = database.table("q").err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "Unknown text encoding in the database header: 0, expected 1 (UTF-8), 2 (UTF-16le) or 3 (UTF-16be)");
+
+    let mut database = Database::builder().unknown_encoding_as_utf8(true).from_bytes(&data).unwrap();
+    let mut table = database.table("q").unwrap().unwrap();
+    let rows = table.rows().collect::<std::io::Result<Vec<Row>>>().unwrap();
+    assert_eq!(rows.iter().map(|row| row.record.entries[1].as_str().unwrap()).collect::<Vec<_>>(), ["plain", "a,b", "say \"hi\"", "two\nlines"]);
+}
+
+#[test]
+fn application_id_and_user_version() {
+    let database = common::open("tagged.db");
+    assert_eq!(database.header().application_id(), 0x4750_4b47);
+    assert_eq!(&database.header().application_id().to_be_bytes(), b"GPKG");
+    assert_eq!(database.header().user_version(), 10300);
+
+    let database = common::open("csv.db");
+    assert_eq!(database.header().application_id(), 0);
+    assert_eq!(database.header().user_version(), 0);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use sqlite_reader::{FilePageType, RecordEntry};
+
+#[test]
+fn descending_index_columns_are_found() {
+    let mut database = common::open("descending.db");
+    let index = database.indexes().unwrap().into_iter().find(|index| index.name == "t_a_b").unwrap();
+    let root_page = index.root_page.unwrap();
+
+    // Entries are stored with `a` in descending order, and `b` ascending within each value of `a`
+    let keys = database.index_entries(root_page)
+        .map(|entry| entry.map(|record| (record.entries[0].as_str().unwrap().to_string(), record.entries[1].as_i64().unwrap())))
+        .collect::<std::io::Result<Vec<_>>>()
+        .unwrap();
+    let mut expected = (1..=500).map(|index| (format!("{:03}", index % 50), index)).collect::<Vec<_>>();
+    expected.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    assert_eq!(keys, expected);
+
+    for a in [0, 7, 25, 49] {
+        let rowids = database.find_index(root_page, &[RecordEntry::Text(format!("{:03}", a))]).unwrap();
+        assert_eq!(rowids, (1..=500).filter(|index| index % 50 == a).collect::<Vec<_>>());
+    }
+
+    let rowids = database.find_index(root_page, &[RecordEntry::Text("007".to_string()), RecordEntry::Integer(257)]).unwrap();
+    assert_eq!(rowids, [257]);
+    assert!(database.find_index(root_page, &[RecordEntry::Text("050".to_string())]).unwrap().is_empty());
+}
+
+#[test]
+fn large_keys_overflow_on_interior_pages() {
+    let mut database = common::open("index_overflow.db");
+    let root_page = database.indexes().unwrap()[0].root_page.unwrap();
+    let text = |index: i64| format!("{:03}{}", index, common::digits(997));
+
+    // The key of an interior cell is a whole index entry, of which only a part is on the page
+    let root = database.page(root_page).unwrap();
+    assert_eq!(root.header.typ, FilePageType::IndexInterior);
+    let cell = &root.cells[0];
+    assert!(cell.first_overflow_page_number.is_some());
+    assert_eq!(cell.payload_length, Some(1005));
+    let key = cell.payload.as_ref().unwrap();
+    let rowid = key.entries[1].as_i64().unwrap();
+    assert_eq!(key.entries[0].as_str(), Some(text(rowid).as_str()));
+
+    let entries = database.index_entries(root_page).collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(entries.len(), 20);
+    for (entry, index) in entries.iter().zip(1..) {
+        assert_eq!(entry.entries[0].as_str(), Some(text(index).as_str()));
+        assert_eq!(entry.entries[1].as_i64(), Some(index));
+    }
+
+    // The interior key itself is found as well as the keys of the leaves
+    for index in [1, rowid, 20] {
+        assert_eq!(database.find_index(root_page, &[RecordEntry::Text(text(index))]).unwrap(), [index]);
+    }
+}
+
+#[test]
+fn indexes_compare_text_with_their_collation() {
+    let mut database = common::open("collations.db");
+    let indexes = database.indexes().unwrap();
+    let root_page = |name: &str| indexes.iter().find(|index| index.name == name).unwrap().root_page.unwrap();
+    let (nocase, rtrim) = (root_page("t_name"), root_page("t_name_rtrim"));
+
+    // Same rowids as `SELECT id FROM t WHERE name = 'alice'` with and without `COLLATE RTRIM`
+    let mut rowids = database.find_index(nocase, &[RecordEntry::Text("alice".to_string())]).unwrap();
+    rowids.sort_unstable();
+    assert_eq!(rowids, [1, 3]);
+    assert_eq!(database.find_index(rtrim, &[RecordEntry::Text("alice".to_string())]).unwrap(), [5]);
+    assert_eq!(database.find_index(rtrim, &[RecordEntry::Text("Alice ".to_string())]).unwrap(), [1]);
+}
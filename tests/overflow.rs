@@ -0,0 +1,42 @@
+mod common;
+
+use std::io::ErrorKind;
+
+use sqlite_reader::{Database, Row};
+
+#[test]
+fn payloads_spanning_several_overflow_pages_read_back_intact() {
+    let mut database = common::open("overflow.db");
+    let mut table = database.table("t").unwrap().unwrap();
+    let rows = table.rows().collect::<std::io::Result<Vec<Row>>>().unwrap();
+
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].record.entries[1].as_str(), Some(common::digits(5000).as_str()));
+    assert_eq!(rows[1].record.entries[2].as_blob(), Some((0..3000).map(|index| (index % 251) as u8).collect::<Vec<_>>().as_slice()));
+    assert_eq!(rows[2].record.entries[1].as_str(), Some("short"));
+}
+
+#[test]
+fn overflow_chain_looping_back_is_an_error() {
+    let mut data = common::read("overflow.db");
+    let (root_page, first_overflow_page) = {
+        let mut database = Database::from_bytes(&data).unwrap();
+        let root_page = database.table("t").unwrap().unwrap().root_page;
+        let first_overflow_page = database.pages()
+            .map(Result::unwrap)
+            .filter(|(page_number, _)| *page_number != 1)
+            .flat_map(|(_, page)| page.cells)
+            .find_map(|cell| cell.first_overflow_page_number.filter(|_| cell.rowid == Some(1)));
+        (root_page, first_overflow_page.unwrap())
+    };
+
+    // Point the first overflow page to itself, a chain that would never end
+    let offset = (first_overflow_page as usize - 1) * 512;
+    data[offset..offset + 4].copy_from_slice(&first_overflow_page.to_be_bytes());
+
+    let mut database = Database::from_bytes(&data).unwrap();
+    let err = database.find_row(root_page, 1).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), format!("Overflow chain starting at page {} loops back to page {}", first_overflow_page, first_overflow_page));
+}
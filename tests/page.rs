@@ -0,0 +1,120 @@
+mod common;
+
+use std::fs::File;
+use std::io::{Cursor, ErrorKind, Seek, SeekFrom};
+
+use sqlite_reader::{Database, FileHeader, FilePage, FilePageType, ReadOptions, TruncatedFile};
+
+/// Reads page 2 of `csv.db`, a table leaf of 512 bytes, with the pointer to its cell `index` replaced.
+fn read_with_cell_offset(index: usize, offset: u16) -> std::io::Result<FilePage> {
+    let mut data = common::read("csv.db");
+    let pointer = 512 + 8 + index * 2;
+    data[pointer..pointer + 2].copy_from_slice(&offset.to_be_bytes());
+
+    let mut reader = Cursor::new(data);
+    let header = FileHeader::read(&mut reader).unwrap();
+    FilePage::read(&mut reader, &header, 2, &ReadOptions::default())
+}
+
+#[test]
+fn page_1_is_read_by_its_number() {
+    let mut file = File::open(common::fixture_path("csv.db")).unwrap();
+    let header = FileHeader::read(&mut file).unwrap();
+
+    // Wherever the reader was left, the b-tree header of page 1 is found after the file header
+    file.seek(SeekFrom::End(0)).unwrap();
+    let page = FilePage::read(&mut file, &header, 1, &ReadOptions::default()).unwrap();
+
+    assert_eq!(FilePage::header_start(1), 100);
+    assert_eq!(page.header.typ, FilePageType::TableLeaf);
+    assert_eq!(page.cells.len(), 2);
+
+    // Cell offsets are relative to the start of the page, which is the start of the file
+    let names = page.cells.iter()
+        .map(|cell| cell.payload.as_ref().unwrap().entries[1].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["q", "w"]);
+    assert!(page.cells.iter().all(|cell| cell.file_offset >= page.content_range().start as u64));
+}
+
+#[test]
+fn cell_offsets_outside_of_the_content_area_are_errors() {
+    assert_eq!(read_with_cell_offset(0, 498).unwrap().cells.len(), 4);
+
+    for offset in [0, 8, 447, 512, 1000, u16::MAX] {
+        let err = read_with_cell_offset(0, offset).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), format!("Cell 0 of page 2 starts at {}, outside of the cell content area", offset));
+    }
+}
+
+#[test]
+fn overlapping_cells_are_errors() {
+    // Pointing two cells at the same offset reads the same cell twice
+    let err = read_with_cell_offset(1, 498).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "Cells 0 and 1 of page 2 overlap at offset 498");
+
+    let err = read_with_cell_offset(3, 464).unwrap_err();
+    assert_eq!(err.to_string(), "Cells 2 and 3 of page 2 overlap at offset 464");
+}
+
+#[test]
+fn every_cell_offset_is_read_without_panicking() {
+    for index in 0..4 {
+        for offset in 0..=u16::MAX {
+            if let Err(err) = read_with_cell_offset(index, offset) {
+                assert_eq!(err.kind(), ErrorKind::InvalidData, "cell {} at {}: {}", index, offset, err);
+            }
+        }
+    }
+}
+
+#[test]
+fn cell_offsets_inside_the_reserved_space_are_errors() {
+    // Page 3 of reserved.db is a table leaf of 1024 bytes, the last 32 of which are reserved
+    let mut data = common::read("reserved.db");
+    data[2048 + 8..2048 + 10].copy_from_slice(&1000u16.to_be_bytes());
+
+    let mut reader = Cursor::new(data);
+    let header = FileHeader::read(&mut reader).unwrap();
+    let err = FilePage::read(&mut reader, &header, 3, &ReadOptions::default()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "Cell 0 of page 3 starts at 1000, inside the reserved space");
+}
+
+#[test]
+fn last_page_cut_short() {
+    // range.db has 52 pages of 512 bytes, the last being a table leaf whose 10 cells take up
+    // 46 bytes each from offset 52, so that the 5 of them below offset 312 are left whole
+    let data = common::read("range.db");
+    let data = &data[..51 * 512 + 312];
+
+    let mut database = Database::from_bytes(data).unwrap();
+    assert_eq!(database.header().database_size(), 52);
+    let err = database.page(52).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    let truncated = err.get_ref().and_then(|inner| inner.downcast_ref::<TruncatedFile>()).unwrap();
+    assert_eq!(*truncated, TruncatedFile {
+        page_number: 52,
+        file_length: 51 * 512 + 312,
+        missing_bytes: 200,
+    });
+    assert_eq!(database.page(51).unwrap().page_number, 51);
+
+    let options = ReadOptions {
+        partial_pages: true,
+        ..ReadOptions::default()
+    };
+    let mut database = Database::builder().read_options(options).from_bytes(data).unwrap();
+    let page = database.page(52).unwrap();
+    assert_eq!(page.header.cells_count, 10);
+    assert_eq!(page.cells.len(), 5);
+    assert_eq!(page.cells.iter().map(|cell| cell.rowid.unwrap()).collect::<Vec<_>>(), [496, 497, 498, 499, 500]);
+    assert!(page.cells.iter().all(|cell| cell.payload.as_ref().unwrap().entries[1].as_str() == Some(common::digits(40).as_str())));
+
+    // The rows of the cells cut off are lost, and all of the others are read
+    let mut table = database.table("t").unwrap().unwrap();
+    let rowids = table.rows().map(|row| row.map(|row| row.rowid.unwrap())).collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(rowids, (1..=490).chain(496..=500).collect::<Vec<_>>());
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use sqlite_reader::{FilePageType, PtrmapEntry, PtrmapPageType, VacuumMode};
+
+/// Checks the pointer map of one of the `autovacuum_*.db` fixtures, returning the free pages it lists.
+fn check_pointer_map(name: &str) -> Vec<u32> {
+    let mut database = common::open(name);
+    let database_size = database.header().database_size();
+    assert!(database.header().is_autovacuum());
+    assert!(database.header().is_ptrmap_page(2));
+    assert_eq!(database.ptrmap_entry(1).unwrap(), None);
+    assert_eq!(database.ptrmap_entry(2).unwrap(), None);
+
+    let entries = (3..=database_size)
+        .map(|page_number| (page_number, database.ptrmap_entry(page_number).unwrap().unwrap()))
+        .collect::<Vec<_>>();
+    let entry = |page_number: u32| entries.iter().find(|(number, _)| *number == page_number).unwrap().1;
+
+    let t = database.table("t").unwrap().unwrap().root_page;
+    let u = database.table("u").unwrap().unwrap().root_page;
+    for root_page in [t, u] {
+        assert_eq!(entry(root_page), PtrmapEntry {
+            page_type: PtrmapPageType::RootPage,
+            parent: 0,
+        });
+    }
+
+    // The children of the interior root of t, and the overflow chain of its first row
+    let root = database.page(t).unwrap();
+    assert_eq!(root.header.typ, FilePageType::TableInterior);
+    let mut children = root.cells.iter().map(|cell| cell.left_child_page_number.unwrap()).collect::<Vec<_>>();
+    children.push(root.right_most_pointer().unwrap());
+    for &child in children.iter() {
+        assert_eq!(entry(child), PtrmapEntry {
+            page_type: PtrmapPageType::BtreePage,
+            parent: t,
+        });
+    }
+
+    let first_leaf = database.page(children[0]).unwrap();
+    let first_overflow_page = first_leaf.cells[0].first_overflow_page_number.unwrap();
+    assert_eq!(entry(first_overflow_page), PtrmapEntry {
+        page_type: PtrmapPageType::FirstOverflowPage,
+        parent: children[0],
+    });
+    for (page_number, entry) in entries.iter().filter(|(_, entry)| entry.page_type == PtrmapPageType::OverflowPage) {
+        assert_ne!(*page_number, first_overflow_page);
+        assert!(database.ptrmap_entry(entry.parent).unwrap().is_some_and(|parent| matches!(parent.page_type, PtrmapPageType::FirstOverflowPage | PtrmapPageType::OverflowPage)));
+    }
+
+    assert!(database.integrity_check().is_empty());
+
+    let free_pages = entries.iter()
+        .filter(|(_, entry)| entry.page_type == PtrmapPageType::FreePage)
+        .map(|(page_number, entry)| {
+            assert_eq!(entry.parent, 0);
+            *page_number
+        })
+        .collect::<Vec<_>>();
+    let mut freelist = database.freelist().unwrap().pages();
+    freelist.sort_unstable();
+    assert_eq!(free_pages, freelist);
+    free_pages
+}
+
+#[test]
+fn pointer_map_in_full_mode() {
+    let database = common::open("autovacuum_full.db");
+    assert_eq!(database.header().vacuum_mode(), VacuumMode::Full);
+
+    // Pages freed by a commit are truncated right away
+    assert!(check_pointer_map("autovacuum_full.db").is_empty());
+}
+
+#[test]
+fn pointer_map_in_incremental_mode() {
+    let database = common::open("autovacuum_incremental.db");
+    assert_eq!(database.header().vacuum_mode(), VacuumMode::Incremental);
+
+    // Pages freed by a commit stay until `PRAGMA incremental_vacuum`
+    assert_eq!(check_pointer_map("autovacuum_incremental.db").len(), 5);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use sqlite_reader::{FilePageType, Schema, SchemaEntryType, SCHEMA_ROOT_PAGE};
+
+#[test]
+fn page_1_is_the_schema_root() {
+    assert_eq!(SCHEMA_ROOT_PAGE, 1);
+
+    let mut database = common::open("csv.db");
+    let root = database.page(SCHEMA_ROOT_PAGE).unwrap();
+    assert_eq!(root.page_number, 1);
+    assert_eq!(root.header.typ, FilePageType::TableLeaf);
+
+    // The tables themselves are rooted after the schema
+    let schema = Schema::read(&mut database).unwrap();
+    let tables = schema.entries.iter()
+        .filter(|entry| entry.typ == SchemaEntryType::Table)
+        .map(|entry| (entry.name.as_str(), entry.root_page.unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(tables, [("q", 2), ("w", 3)]);
+}
+
+#[test]
+fn tables_and_indexes_of_a_schema() {
+    let mut database = common::open("catalog.db");
+
+    // Views and triggers have no b-tree and are neither tables nor indexes
+    let tables = database.tables().unwrap().into_iter()
+        .map(|table| (table.name, table.root_page))
+        .collect::<Vec<_>>();
+    assert_eq!(tables, [
+        ("users".to_string(), Some(2)),
+        ("sqlite_sequence".to_string(), Some(4)),
+        ("posts".to_string(), Some(5)),
+    ]);
+
+    let indexes = database.indexes().unwrap().into_iter()
+        .map(|index| (index.name, index.table_name, index.root_page, index.automatic))
+        .collect::<Vec<_>>();
+    assert_eq!(indexes, [
+        ("sqlite_autoindex_users_1".to_string(), "users".to_string(), Some(3), true),
+        ("posts_user".to_string(), "posts".to_string(), Some(6), false),
+        ("posts_user_created".to_string(), "posts".to_string(), Some(8), false),
+    ]);
+
+    let schema = Schema::read(&mut database).unwrap();
+    let others = schema.entries.iter()
+        .filter(|entry| !matches!(entry.typ, SchemaEntryType::Table | SchemaEntryType::Index))
+        .map(|entry| (entry.typ, entry.name.as_str(), entry.table_name.as_str(), entry.root_page))
+        .collect::<Vec<_>>();
+    assert_eq!(others, [
+        (SchemaEntryType::View, "titles", "titles", None),
+        (SchemaEntryType::Trigger, "users_delete", "users", None),
+    ]);
+
+    // Every root page leads to a b-tree of the expected kind
+    for (_, root_page) in tables {
+        assert!(matches!(database.page(root_page.unwrap()).unwrap().header.typ, FilePageType::TableLeaf | FilePageType::TableInterior));
+    }
+    for (_, _, root_page, _) in indexes {
+        assert!(matches!(database.page(root_page.unwrap()).unwrap().header.typ, FilePageType::IndexLeaf | FilePageType::IndexInterior));
+    }
+}
+
+#[test]
+fn indexed_columns_and_tables() {
+    let mut database = common::open("catalog.db");
+    let indexes = database.indexes().unwrap();
+
+    // Which indexes exist on posts, and what they index, with the DESC clause left out
+    let posts = indexes.iter()
+        .filter(|index| index.table_name == "posts")
+        .map(|index| (index.name.as_str(), index.columns.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(posts, [
+        ("posts_user", vec!["user_id".to_string()]),
+        ("posts_user_created", vec!["user_id".to_string(), "created".to_string()]),
+    ]);
+
+    // The index of a UNIQUE constraint has no SQL to take its columns from
+    let users = indexes.iter().filter(|index| index.table_name == "users").collect::<Vec<_>>();
+    assert_eq!(users.len(), 1);
+    assert!(users[0].automatic);
+    assert!(users[0].columns.is_empty());
+
+    let mut database = common::open("collations.db");
+    let columns = database.indexes().unwrap().into_iter().map(|index| index.columns).collect::<Vec<_>>();
+    assert_eq!(columns, [vec!["name".to_string()], vec!["name".to_string()]]);
+}
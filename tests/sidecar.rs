@@ -0,0 +1,57 @@
+mod common;
+
+use std::io::Cursor;
+
+use sqlite_reader::{Database, Journal};
+
+/// Opens a copy of `csv.db` next to a `-wal` file with the given content, and reads the
+/// texts of table `q`.
+fn texts_with_wal(test: &str, wal: &[u8]) -> Vec<String> {
+    let path = common::copy("csv.db", test);
+    std::fs::write(format!("{}-wal", path), wal).unwrap();
+    let mut database = Database::open(&path).unwrap();
+    let mut table = database.table("q").unwrap().unwrap();
+    table.rows().map(|row| row.map(|row| row.record.entries[1].as_str().unwrap().to_string())).collect::<std::io::Result<Vec<_>>>().unwrap()
+}
+
+#[test]
+fn garbage_wal_is_ignored() {
+    let texts = texts_with_wal("garbage-wal", &[0x5a; 64]);
+    assert_eq!(texts, ["plain", "a,b", "say \"hi\"", "two\nlines"]);
+}
+
+#[test]
+fn wal_with_invalid_page_size_is_ignored() {
+    let mut wal = vec![0; 32];
+    wal[0..4].copy_from_slice(&0x377f0682u32.to_be_bytes());
+    wal[4..8].copy_from_slice(&3007000u32.to_be_bytes());
+    wal[8..12].copy_from_slice(&1000u32.to_be_bytes());
+    let texts = texts_with_wal("wal-page-size", &wal);
+    assert_eq!(texts, ["plain", "a,b", "say \"hi\"", "two\nlines"]);
+}
+
+/// Header of a rollback journal with the given page size and sector size.
+fn journal_header(page_size: u32, sector_size: u32) -> Vec<u8> {
+    let mut header = vec![0; 512];
+    header[0..8].copy_from_slice(&[0xd9, 0xd5, 0x05, 0xf9, 0x20, 0xa1, 0x63, 0xd7]);
+    header[16..20].copy_from_slice(&4u32.to_be_bytes());
+    header[20..24].copy_from_slice(&sector_size.to_be_bytes());
+    header[24..28].copy_from_slice(&page_size.to_be_bytes());
+    header
+}
+
+#[test]
+fn journal_with_invalid_header_is_not_hot() {
+    for (test, page_size, sector_size) in [("journal-page-size", 1000, 512), ("journal-sector-size", 512, 3)] {
+        let header = journal_header(page_size, sector_size);
+        let journal = Journal::from_reader(Cursor::new(header.clone())).unwrap();
+        assert!(!journal.is_hot());
+        assert!(journal.dirty_pages().is_empty());
+
+        let path = common::copy("csv.db", test);
+        std::fs::write(format!("{}-journal", path), header).unwrap();
+        let mut database = Database::open(&path).unwrap();
+        assert!(database.hot_journal().is_none());
+        assert_eq!(database.table("q").unwrap().unwrap().rows().count(), 4);
+    }
+}
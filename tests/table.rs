@@ -0,0 +1,122 @@
+mod common;
+
+use std::borrow::Cow;
+
+use sqlite_reader::{RecordEntry, Row};
+
+/// Reads every row of a table of a fixture.
+fn rows(name: &str, table: &str) -> Vec<Row> {
+    let mut database = common::open(name);
+    let mut table = database.table(table).unwrap().unwrap();
+    table.rows().collect::<std::io::Result<_>>().unwrap()
+}
+
+#[test]
+fn negative_rowids_and_integers() {
+    let rows = rows("negative.db", "t");
+    let expected = [i64::MIN, -1, 0, 1, i64::MAX];
+
+    assert_eq!(rows.iter().map(|row| row.rowid.unwrap()).collect::<Vec<_>>(), expected);
+    assert_eq!(rows.iter().map(|row| row.record.entries[0].as_i64().unwrap()).collect::<Vec<_>>(), expected);
+    assert_eq!(rows.iter().map(|row| row.record.entries[1].as_i64().unwrap()).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn negative_rowids_are_found() {
+    let mut database = common::open("negative.db");
+    let root_page = database.table("t").unwrap().unwrap().root_page;
+
+    for rowid in [i64::MIN, -1] {
+        let record = database.find_row(root_page, rowid).unwrap().unwrap();
+        assert_eq!(record.entries[1].as_i64(), Some(rowid));
+    }
+}
+
+#[test]
+fn rowid_alias_is_rendered_like_the_sqlite3_shell() {
+    let mut database = common::open("rowid_alias.db");
+    let mut table = database.table("t").unwrap().unwrap();
+    let rows = table.rows().collect::<std::io::Result<Vec<_>>>().unwrap();
+
+    // Output of `sqlite3 rowid_alias.db "SELECT * FROM t"` in the list and JSON modes
+    let lines = rows.iter().map(|row| row.record.to_string()).collect::<Vec<_>>();
+    assert_eq!(lines, ["c|-3|2", "a|10|", "b|20|1"]);
+
+    let objects = rows.iter().map(|row| row.to_json(&table.def)).collect::<Vec<_>>();
+    assert_eq!(objects, [
+        r#"{"name":"c","id":-3,"n":2}"#,
+        r#"{"name":"a","id":10,"n":null}"#,
+        r#"{"name":"b","id":20,"n":1}"#,
+    ]);
+}
+
+#[test]
+fn generated_columns_keep_the_others_aligned() {
+    let mut database = common::open("generated.db");
+    let mut table = database.table("t").unwrap().unwrap();
+    assert_eq!(table.def.storage_index(1), None);
+    assert_eq!(table.def.storage_index(3), Some(2));
+
+    // The STORED column is in the record where it is declared, and the VIRTUAL one is not stored,
+    // so it reads as NULL in declared order
+    let rows = table.rows().collect::<std::io::Result<Vec<_>>>().unwrap();
+    let lines = rows.iter().map(|row| row.record.to_string()).collect::<Vec<_>>();
+    assert_eq!(lines, ["1||x|x!|p", "2||y|y!|q"]);
+
+    let d = rows.iter().map(|row| row.record.with_columns(&table.def).get_by_name("d").unwrap().as_str().unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(d, ["x!", "y!"]);
+    let e = rows.iter().map(|row| row.record.with_columns(&table.def).get_by_name("e").unwrap().as_str().unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(e, ["p", "q"]);
+}
+
+#[test]
+fn added_columns_read_as_their_default() {
+    let mut database = common::open("added_columns.db");
+    let mut table = database.table("t").unwrap().unwrap();
+
+    let columns = table.def.columns.iter()
+        .map(|column| (column.name.as_str(), column.not_null, column.default.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(columns, [
+        ("id", false, None),
+        ("a", false, None),
+        ("b", true, Some("7")),
+        ("c", false, Some("'none'")),
+        ("d", false, Some("(-1)")),
+        ("e", false, None),
+    ]);
+
+    // Same as `sqlite3 added_columns.db "SELECT * FROM t"`, but for the blob written as a literal
+    let rows = table.rows().collect::<std::io::Result<Vec<_>>>().unwrap();
+    let lines = rows.iter().map(|row| row.record.to_string()).collect::<Vec<_>>();
+    assert_eq!(lines, ["1|x|7|none|-1.0|", "2|y|7|none|-1.0|", "3|z|8|some|2.5|x'01'"]);
+}
+
+#[test]
+fn short_and_full_records_of_a_table() {
+    let mut database = common::open("added_columns.db");
+    let table = database.table("t").unwrap().unwrap();
+    let (root_page, def) = (table.root_page, table.def);
+
+    // As stored, the rows written before the columns were added only have the first two
+    let stored = database.rows(root_page).collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(stored.iter().map(|(_, record)| record.len()).collect::<Vec<_>>(), [2, 2, 6]);
+
+    let (short, full) = (stored[0].1.with_columns(&def), stored[2].1.with_columns(&def));
+    assert!(matches!(short.get_by_name("c"), Some(Cow::Owned(RecordEntry::Text(text))) if text == "none"));
+    assert!(matches!(full.get_by_name("C"), Some(Cow::Borrowed(RecordEntry::Text(text))) if text == "some"));
+    assert!(matches!(short.get_by_name("e"), Some(Cow::Owned(RecordEntry::Null))));
+    assert!(matches!(full.get_by_name("e").as_deref(), Some(RecordEntry::Blob(blob)) if blob == &[1]));
+    assert!(short.get_by_name("f").is_none());
+
+    let pairs = short.iter().map(|(name, entry)| format!("{}={}", name, entry)).collect::<Vec<_>>();
+    assert_eq!(pairs, ["id=", "a=x", "b=7", "c=none", "d=-1.0", "e="]);
+    let pairs = full.iter().map(|(name, entry)| format!("{}={}", name, entry)).collect::<Vec<_>>();
+    assert_eq!(pairs, ["id=", "a=z", "b=8", "c=some", "d=2.5", "e=x'01'"]);
+
+    // Rows of the table come with every column, the missing ones holding their default
+    let mut table = database.table("t").unwrap().unwrap();
+    let rows = table.rows().collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert!(rows.iter().all(|row| row.record.len() == 6));
+    assert_eq!(rows[1].to_json(&def), r#"{"id":2,"a":"y","b":7,"c":"none","d":-1.0,"e":null}"#);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use std::io::ErrorKind;
+use std::ops::ControlFlow;
+
+use sqlite_reader::{walk_table, Database, FilePage, FilePageHeader, Comparison, FilePageType, Filter, ReadOptions, RecordEntry};
+
+#[test]
+fn rowid_ranges_straddling_interior_keys_miss_no_rows() {
+    let mut database = common::open("range.db");
+    let root_page = database.table("t").unwrap().unwrap().root_page;
+
+    let root = database.page(root_page).unwrap();
+    assert_eq!(root.header.typ, FilePageType::TableInterior);
+    let keys = root.cells.iter().map(|cell| cell.rowid.unwrap()).collect::<Vec<_>>();
+    assert!(keys.len() > 1);
+
+    // Each key is the largest rowid of its left subtree, the next rowid being in the subtree to its right
+    for key in keys {
+        for (min_rowid, max_rowid) in [(key, key + 1), (key - 1, key), (key, key), (key + 1, key + 1), (key - 1, key + 2)] {
+            let filter = Filter {
+                min_rowid: Some(min_rowid),
+                max_rowid: Some(max_rowid),
+                ..Filter::default()
+            };
+
+            let mut rowids = Vec::new();
+            let summary = walk_table(database.pager(), root_page, &filter, |rowid, _| {
+                rowids.push(rowid);
+                Ok(ControlFlow::Continue(()))
+            }).unwrap();
+
+            assert!(summary.errors.is_empty());
+            assert_eq!(rowids, (min_rowid..=max_rowid).collect::<Vec<_>>(), "rowids from {} to {}", min_rowid, max_rowid);
+        }
+    }
+}
+
+#[test]
+fn interior_page_truncated_before_its_right_most_pointer() {
+    // The root of range.db is page 2, whose right-most pointer is at bytes 8 to 12 of the page
+    let data = common::read("range.db");
+    let mut database = Database::from_bytes(&data[..512 + 10]).unwrap();
+    let root_page = database.table("t").unwrap().unwrap().root_page;
+    assert_eq!(root_page, 2);
+
+    let err = database.page(root_page).unwrap_err();
+    assert_eq!(err.to_string(), "File is truncated: it ends at offset 522, 502 bytes short of the end of page 2");
+
+    let summary = walk_table(database.pager(), root_page, &Filter::default(), |_, _| Ok(ControlFlow::Continue(()))).unwrap();
+    assert_eq!(summary.pages_visited, 1);
+    assert_eq!(summary.errors.len(), 1);
+    assert_eq!(summary.errors[0].to_string(), format!("Page 2 at offset 512 could not be read: {}", err));
+
+    // Reading what is left of the page still needs all of its header
+    let options = ReadOptions {
+        partial_pages: true,
+        ..ReadOptions::default()
+    };
+    let mut database = Database::builder().read_options(options).from_bytes(&data[..512 + 10]).unwrap();
+    let err = database.page(root_page).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "Page 2 is truncated: the file ends inside its header at 522");
+}
+
+#[test]
+fn right_most_pointers_of_interior_pages_only() {
+    let mut database = common::open("range.db");
+    let root = database.page(2).unwrap();
+    let child = root.right_most_pointer().unwrap();
+
+    let leaf = database.page(child).unwrap();
+    assert_eq!(leaf.header.typ, FilePageType::TableLeaf);
+    assert_eq!(leaf.right_most_pointer().unwrap_err().to_string(), format!("Leaf page {} has no right-most pointer", child));
+
+    let page = FilePage {
+        page_number: 2,
+        header: FilePageHeader {
+            typ: FilePageType::TableInterior,
+            first_free_block: 0,
+            cells_count: 0,
+            cells_content_start: 0,
+            cells_content_fragmented_bytes: 0,
+            right_most_pointer: None,
+        },
+        cells: Vec::new(),
+        freeblocks: Vec::new(),
+        usable_size: root.usable_size,
+    };
+    assert_eq!(page.right_most_pointer().unwrap_err().to_string(), "Interior page 2 has no right-most pointer");
+}
+
+#[test]
+fn column_filters_compare_text_with_the_collation_of_the_column() {
+    let mut database = common::open("collations.db");
+    let table = database.table("t").unwrap().unwrap();
+    let (root_page, def) = (table.root_page, table.def);
+
+    // Same rowids as `SELECT id FROM t WHERE name = 'alice'` and `WHERE name > 'b'`
+    for (comparison, value, expected) in [(Comparison::Equal, "alice", vec![1, 3]), (Comparison::Greater, "b", vec![2, 4])] {
+        let filter = Filter::default().and_table_column(&def, 1, comparison, RecordEntry::Text(value.to_string()));
+        let mut rowids = Vec::new();
+        walk_table(database.pager(), root_page, &filter, |rowid, _| {
+            rowids.push(rowid);
+            Ok(ControlFlow::Continue(()))
+        }).unwrap();
+        assert_eq!(rowids, expected);
+    }
+}